@@ -1,15 +1,22 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::hash::Hash;
+use std::ops::ControlFlow;
+use super::aho_corasick_file::StreamSearch;
+use crate::error::Error;
 
-pub struct AhoCorasickNode {
-    pub children: HashMap<char, usize>,
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+pub struct AhoCorasickNode<T = char> {
+    pub children: HashMap<T, usize>,
     pub suffix_link: Option<usize>,
     pub output_links: HashSet<usize>,
     pub length: usize,
 }
 
-impl AhoCorasickNode {
+impl<T: Eq + Hash + Clone> AhoCorasickNode<T> {
     pub fn new() -> Self {
         Self {
             children: HashMap::new(),
@@ -20,177 +27,390 @@ impl AhoCorasickNode {
     }
 }
 
-pub struct AhoCorasick {
-    pub nodes: HashMap<usize, AhoCorasickNode>,
+impl<T: Eq + Hash + Clone> Default for AhoCorasickNode<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * limits enforced by the try_ functions when inserting patterns
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_pattern_length: usize,
+    pub max_nodes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_pattern_length: usize::MAX,
+            max_nodes: usize::MAX
+        }
+    }
+}
+
+/**
+ * the automaton is generic over the symbol type so that patterns can be sequences of anything comparable,
+ * ex: words of a tokenized text or integer token ids
+ * char is the default symbol type and has the str based functions
+ *
+ * the try_ functions return an error instead of panicking when the nodes are inconsistent, ex: after an external modification of the nodes
+ * the other functions never panic. they ignore the error and return an empty result instead
+ */
+pub struct AhoCorasick<T = char> {
+    pub nodes: HashMap<usize, AhoCorasickNode<T>>,
     pub root: usize,
-    new_node_id: usize
+    new_node_id: usize,
+    limits: Limits,
+    // number of patterns per length so that the longest one is known without scanning the nodes
+    pattern_lengths: HashMap<usize, usize>,
+    max_pattern_length: usize
+}
+
+impl<T: Eq + Hash + Clone> Default for AhoCorasick<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl AhoCorasick {
+impl<T: Eq + Hash + Clone> AhoCorasick<T> {
     pub fn new() -> Self {
+        Self::with_limits(Limits::default())
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
         let mut nodes = HashMap::new();
         let mut new_node_id = 0;
         let root = new_node_id;
         new_node_id += 1;
         nodes.insert(root, AhoCorasickNode::new());
 
-        Self { nodes, root, new_node_id }
+        Self { nodes, root, new_node_id, limits, pattern_lengths: HashMap::new(), max_pattern_length: 0 }
     }
 
-    fn delete_trie_node(&mut self, node_id: usize, word: &Vec<char>, depth: usize) -> bool {
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn node(&self, node_id: usize) -> Result<&AhoCorasickNode<T>, Error> {
+        self.nodes.get(&node_id).ok_or(Error::MissingNode(node_id))
+    }
+
+    fn node_mut(&mut self, node_id: usize) -> Result<&mut AhoCorasickNode<T>, Error> {
+        self.nodes.get_mut(&node_id).ok_or(Error::MissingNode(node_id))
+    }
+
+    pub fn suffix_link(&self, node_id: usize) -> Result<usize, Error> {
+        self.node(node_id)?.suffix_link.ok_or(Error::MissingSuffixLink(node_id))
+    }
+
+    /**
+     * returns the start index of a pattern ending at end_index
+     */
+    pub fn match_start(&self, output_node_id: usize, end_index: usize) -> Result<(usize, usize), Error> {
+        let length = self.node(output_node_id)?.length;
+        let start = end_index.checked_sub(length).ok_or(Error::InvalidLength(output_node_id))?;
+
+        Ok((start, length))
+    }
+
+    fn delete_trie_node(&mut self, node_id: usize, word: &[T], depth: usize) -> Result<bool, Error> {
         if depth == word.len() {
-            if self.nodes.get(&node_id).unwrap().length == 0 {
-                return false;
+            if self.node(node_id)?.length == 0 {
+                return Ok(false);
             }
 
-            self.nodes.get_mut(&node_id).unwrap().length = 0;
-            return self.nodes.get(&node_id).unwrap().children.is_empty();
+            let node = self.node_mut(node_id)?;
+            let length = node.length;
+            node.length = 0;
+            let is_leaf = node.children.is_empty();
+            self.remove_pattern_length(length);
+            return Ok(is_leaf);
         }
 
-        let c = word[depth];
+        let c = &word[depth];
 
-        if !self.nodes.get(&node_id).unwrap().children.contains_key(&c) {
-            return false;
-        }
+        let child_id = match self.node(node_id)?.children.get(c) {
+            Some(&child_id) => child_id,
+            None => return Ok(false)
+        };
 
-        let child_id = self.nodes.get(&node_id).unwrap().children.get(&c).unwrap();
-        let should_delete_child = self.delete_trie_node(*child_id, word, depth + 1);
+        let should_delete_child = self.delete_trie_node(child_id, word, depth + 1)?;
 
         if should_delete_child {
-            let deleting_id = self.nodes.get_mut(&node_id).unwrap().children.remove(&c).unwrap();
-            self.nodes.remove(&deleting_id);
+            let current_node = self.node_mut(node_id)?;
+            current_node.children.remove(c);
+            let should_delete_current = current_node.children.is_empty() && current_node.length == 0;
+            self.nodes.remove(&child_id);
 
-            let current_node = self.nodes.get(&node_id).unwrap();
-            return current_node.children.is_empty() && current_node.length == 0;
+            return Ok(should_delete_current);
         }
 
-        false
+        Ok(false)
     }
 
-    pub fn build(&mut self, words: Vec<&str>) {
+    pub fn build_sequences(&mut self, words: Vec<&[T]>) {
+        let _ = self.try_build_sequences(words);
+    }
+
+    /**
+     * inserts the patterns in order and stops at the first one that fails
+     * the patterns inserted before the failure are kept and are searchable
+     */
+    pub fn try_build_sequences(&mut self, words: Vec<&[T]>) -> Result<(), Error> {
+        let mut result = Ok(());
+
         for word in words {
-            self.insert(word);
+            result = self.insert_trie(word);
+
+            if result.is_err() {
+                break;
+            }
         }
 
-        self.construct_links();
+        self.construct_links()?;
+        result
     }
 
-    fn insert_trie(&mut self, word: &str) {
+    /**
+     * checks the limits before modifying the trie so a failed insert leaves the trie unchanged
+     */
+    fn check_limits(&self, word: &[T]) -> Result<(), Error> {
+        if word.len() > self.limits.max_pattern_length {
+            return Err(Error::PatternTooLong { length: word.len(), limit: self.limits.max_pattern_length });
+        }
+
         let mut node_id = self.root;
+        let mut existing_depth = 0;
 
-        for c in word.chars() {
-            if !self.nodes.get(&node_id).unwrap().children.contains_key(&c) {
-                let new_id = self.new_node_id;
-                self.new_node_id += 1;
-                self.nodes.insert(new_id, AhoCorasickNode::new());
-                self.nodes.get_mut(&node_id).unwrap().children.insert(c, new_id);
+        for c in word {
+            match self.node(node_id)?.children.get(c) {
+                Some(&child_id) => {
+                    node_id = child_id;
+                    existing_depth += 1;
+                }
+                None => break
             }
+        }
+
+        let new_nodes = word.len() - existing_depth;
 
-            node_id = *self.nodes.get(&node_id).unwrap().children.get(&c).unwrap();
+        if self.nodes.len().saturating_add(new_nodes) > self.limits.max_nodes {
+            return Err(Error::NodeLimitExceeded { limit: self.limits.max_nodes });
         }
 
-        let new_node = self.nodes.get_mut(&node_id).unwrap();
+        Ok(())
+    }
+
+    fn insert_trie(&mut self, word: &[T]) -> Result<(), Error> {
+        self.check_limits(word)?;
+
+        let mut node_id = self.root;
+
+        for c in word {
+            node_id = match self.node(node_id)?.children.get(c) {
+                Some(&child_id) => child_id,
+                None => {
+                    let new_id = self.new_node_id;
+                    self.new_node_id += 1;
+                    self.nodes.insert(new_id, AhoCorasickNode::new());
+                    self.node_mut(node_id)?.children.insert(c.clone(), new_id);
+                    new_id
+                }
+            };
+        }
+
+        let new_node = self.node_mut(node_id)?;
         new_node.output_links.insert(node_id);
-        new_node.length = word.len(); // height at the node is the length of the string
+        let previous_length = new_node.length;
+        new_node.length = word.len(); // height at the node is the length of the sequence
+
+        if previous_length == 0 && !word.is_empty() {
+            *self.pattern_lengths.entry(word.len()).or_insert(0) += 1;
+            self.max_pattern_length = self.max_pattern_length.max(word.len());
+        }
+
+        Ok(())
     }
 
-    pub fn insert(&mut self, word: &str) {
-        self.insert_trie(word);
-        self.construct_links();
+    fn remove_pattern_length(&mut self, length: usize) {
+        if let Some(count) = self.pattern_lengths.get_mut(&length) {
+            *count -= 1;
+
+            if *count == 0 {
+                self.pattern_lengths.remove(&length);
+
+                if length == self.max_pattern_length {
+                    self.max_pattern_length = self.pattern_lengths.keys().copied().max().unwrap_or(0);
+                }
+            }
+        }
     }
 
-    pub fn search(&self, string: &str) -> Vec<(usize, usize)> {
-        let mut node = self.root;
+    pub fn insert_sequence(&mut self, word: &[T]) {
+        let _ = self.try_insert_sequence(word);
+    }
+
+    pub fn try_insert_sequence(&mut self, word: &[T]) -> Result<(), Error> {
+        self.insert_trie(word)?;
+        self.construct_links()
+    }
+
+    /**
+     * returns a vector of tuples (index, length) where both are counted in symbols
+     */
+    pub fn search_sequence(&self, string: &[T]) -> Vec<(usize, usize)> {
+        self.try_search_sequence(string).unwrap_or_default()
+    }
+
+    pub fn try_search_sequence(&self, string: &[T]) -> Result<Vec<(usize, usize)>, Error> {
         let mut output = Vec::new();
 
+        let _ = self.try_search_sequence_with(string, |found| {
+            output.push(found);
+            ControlFlow::<()>::Continue(())
+        })?;
+
+        Ok(output)
+    }
+
+    /**
+     * calls the callback with a tuple (index, length) for every match in the same order as search_sequence
+     * the search stops when the callback returns break and the break value is returned
+     */
+    pub fn search_sequence_with<B, F: FnMut((usize, usize)) -> ControlFlow<B>>(&self, string: &[T], callback: F) -> ControlFlow<B> {
+        self.try_search_sequence_with(string, callback).unwrap_or(ControlFlow::Continue(()))
+    }
+
+    pub fn try_search_sequence_with<B, F: FnMut((usize, usize)) -> ControlFlow<B>>(&self, string: &[T], mut callback: F) -> Result<ControlFlow<B>, Error> {
+        let mut node = self.root;
+
         // empty string case
         // only the root's output set's size is check because the empty string has no length
-        if !self.nodes.get(&node).unwrap().output_links.is_empty() {
-            output.push((0, 0)); // no need to iterate through output links
+        if !self.node(node)?.output_links.is_empty() {
+            // no need to iterate through output links
+            if let ControlFlow::Break(value) = callback((0, 0)) {
+                return Ok(ControlFlow::Break(value));
+            }
         }
 
-        let characters: Vec<_> = string.chars().collect();
         let mut i = 0;
 
-        while i < characters.len() {
-            let c = characters[i];
+        while i < string.len() {
+            let c = &string[i];
 
-            if let Some(&next) = self.nodes.get(&node).unwrap().children.get(&c) {
+            if let Some(&next) = self.node(node)?.children.get(c) {
                 node = next;
                 i += 1;
 
-                for &out_node in &self.nodes.get(&node).unwrap().output_links {
-                    let len = self.nodes.get(&out_node).unwrap().length;
-                    output.push((i - len, len));
+                for &out_node in &self.node(node)?.output_links {
+                    if let ControlFlow::Break(value) = callback(self.match_start(out_node, i)?) {
+                        return Ok(ControlFlow::Break(value));
+                    }
                 }
             }
             else if node == self.root {
                 i += 1;
             }
             else {
-                node = self.nodes.get(&node).unwrap().suffix_link.unwrap();
+                node = self.suffix_link(node)?;
             }
         }
 
-        output
+        Ok(ControlFlow::Continue(()))
     }
 
-    pub fn remove(&mut self, word: &str) {
-        self.delete_trie_node(self.root, &word.chars().collect(), 0);
-        self.delete_links();
-        self.construct_links();
+    pub fn remove_sequence(&mut self, word: &[T]) {
+        let _ = self.try_remove_sequence(word);
+    }
+
+    pub fn try_remove_sequence(&mut self, word: &[T]) -> Result<(), Error> {
+        self.delete_trie_node(self.root, word, 0)?;
+        self.delete_links()?;
+        self.construct_links()
     }
 
-    fn construct_links(&mut self) {
+    /**
+     * returns the id of the node that ends the pattern, or None when the sequence is not a pattern
+     */
+    pub fn find_sequence(&self, word: &[T]) -> Option<usize> {
+        let mut node_id = self.root;
+
+        for c in word {
+            node_id = *self.nodes.get(&node_id)?.children.get(c)?;
+        }
+
+        let node = self.nodes.get(&node_id)?;
+
+        if node.length == word.len() && node.output_links.contains(&node_id) {
+            Some(node_id)
+        }
+        else {
+            None
+        }
+    }
+
+    /**
+     * returns the length of the longest pattern in symbols
+     * it is kept up to date by the insert and remove functions so the nodes are not scanned
+     */
+    pub fn max_pattern_length(&self) -> usize {
+        self.max_pattern_length
+    }
+
+    fn construct_links(&mut self) -> Result<(), Error> {
         // BFS
         let mut node_queue: VecDeque<usize> = VecDeque::new();
+        let root_children: Vec<usize> = self.node(self.root)?.children.values().copied().collect();
 
-        for (&_key, &value) in self.nodes.get(&self.root).unwrap().children.clone().iter() {
-            self.nodes.get_mut(&value).unwrap().suffix_link = Some(self.root);
+        for value in root_children {
+            self.node_mut(value)?.suffix_link = Some(self.root);
             node_queue.push_back(value);
         }
 
         while let Some(current) = node_queue.pop_front() {
-            let current_node = &mut self.nodes.get(&current).unwrap();
-            let keys: Vec<char> = current_node.children.keys().cloned().collect();
+            let children: Vec<(T, usize)> = self.node(current)?.children.iter().map(|(key, &child_id)| (key.clone(), child_id)).collect();
 
-            for key in keys {
-                let child_id = *self.nodes.get(&current).unwrap().children.get(&key).unwrap();
+            for (key, child_id) in children {
                 node_queue.push_back(child_id);
 
                 // output links
-                let mut failure_node_id = self.nodes.get(&current).unwrap().suffix_link;
+                let mut failure_node_id = self.node(current)?.suffix_link;
 
                 while let Some(current_failure_node_id) = failure_node_id {
-                    if self.nodes.get(&current_failure_node_id).unwrap().children.contains_key(&key) {
+                    if self.node(current_failure_node_id)?.children.contains_key(&key) {
                         break;
                     }
 
-                    failure_node_id = self.nodes.get(&current_failure_node_id).unwrap().suffix_link;
+                    failure_node_id = self.node(current_failure_node_id)?.suffix_link;
                 }
 
-                if let Some(failure_node_id_unrapped) = failure_node_id {
-                    self.nodes.get_mut(&child_id).unwrap().suffix_link = self.nodes.get(&failure_node_id_unrapped).unwrap().children.get(&key).copied();
-                }
-                else {
-                    self.nodes.get_mut(&child_id).unwrap().suffix_link = Some(self.root);
-                }
+                let suffix_node_id = match failure_node_id {
+                    Some(failure_node_id_unrapped) => *self.node(failure_node_id_unrapped)?.children.get(&key).ok_or(Error::MissingNode(failure_node_id_unrapped))?,
+                    None => self.root
+                };
+
+                let output_clone = self.node(suffix_node_id)?.output_links.clone();
+                let child = self.node_mut(child_id)?;
+                child.suffix_link = Some(suffix_node_id);
 
-                let suffix_node_id = self.nodes.get(&child_id).unwrap().suffix_link.unwrap();
-                let output_clone = self.nodes.get(&suffix_node_id).unwrap().output_links.clone();
                 for output_node in output_clone {
-                    self.nodes.get_mut(&child_id).unwrap().output_links.insert(output_node);
+                    child.output_links.insert(output_node);
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn delete_links(&mut self) {
+    fn delete_links(&mut self) -> Result<(), Error> {
         // DFS because of stack implementation time complexity. traversal order does not matter
         let mut stack = vec![self.root];
 
         while let Some(id) = stack.pop() {
-            let aho_corasick_node = self.nodes.get_mut(&id).unwrap();
+            let aho_corasick_node = self.node_mut(id)?;
 
             aho_corasick_node.suffix_link = None;
             aho_corasick_node.output_links.clear();
@@ -203,66 +423,151 @@ impl AhoCorasick {
                 stack.push(child);
             }
         }
+
+        Ok(())
+    }
+}
+
+impl AhoCorasick<char> {
+    pub fn build(&mut self, words: Vec<&str>) {
+        let _ = self.try_build(words);
+    }
+
+    pub fn try_build(&mut self, words: Vec<&str>) -> Result<(), Error> {
+        let sequences: Vec<Vec<char>> = words.iter().map(|word| word.chars().collect()).collect();
+        self.try_build_sequences(sequences.iter().map(|sequence| sequence.as_slice()).collect())
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        let _ = self.try_insert(word);
+    }
+
+    pub fn try_insert(&mut self, word: &str) -> Result<(), Error> {
+        self.try_insert_sequence(&word.chars().collect::<Vec<char>>())
+    }
+
+    pub fn find(&self, word: &str) -> Option<usize> {
+        self.find_sequence(&word.chars().collect::<Vec<char>>())
+    }
+
+    /**
+     * returns a vector of tuples (index, length) where both are counted in chars
+     */
+    pub fn search(&self, string: &str) -> Vec<(usize, usize)> {
+        self.search_sequence(&string.chars().collect::<Vec<char>>())
+    }
+
+    pub fn try_search(&self, string: &str) -> Result<Vec<(usize, usize)>, Error> {
+        self.try_search_sequence(&string.chars().collect::<Vec<char>>())
+    }
+
+    /**
+     * calls the callback with a tuple (index, length) in chars for every match
+     * the search stops when the callback returns break and the break value is returned
+     */
+    pub fn search_with<B, F: FnMut((usize, usize)) -> ControlFlow<B>>(&self, string: &str, callback: F) -> ControlFlow<B> {
+        self.search_sequence_with(&string.chars().collect::<Vec<char>>(), callback)
+    }
+
+    pub fn try_search_with<B, F: FnMut((usize, usize)) -> ControlFlow<B>>(&self, string: &str, callback: F) -> Result<ControlFlow<B>, Error> {
+        self.try_search_sequence_with(&string.chars().collect::<Vec<char>>(), callback)
+    }
+
+    pub fn remove(&mut self, word: &str) {
+        let _ = self.try_remove(word);
+    }
+
+    pub fn try_remove(&mut self, word: &str) -> Result<(), Error> {
+        self.try_remove_sequence(&word.chars().collect::<Vec<char>>())
     }
 
     /**
      * returns a vector of tuples (global index, length, line number, line index)
+     * the index and the length are in chars, the line number and the line index of the match start start at 1
+     * the lines are tracked like in search_reader so both report the same line for a match
      */
     pub fn search_verbose(&self, string: &str) -> Vec<(usize, usize, usize, usize)> {
-        let mut node = self.root;
+        self.try_search_verbose(string).unwrap_or_default()
+    }
+
+    pub fn try_search_verbose(&self, string: &str) -> Result<Vec<(usize, usize, usize, usize)>, Error> {
         let mut output = Vec::new();
 
         // empty string case
         // only the root's output set's size is check because the empty string has no length
-        if !self.nodes.get(&node).unwrap().output_links.is_empty() {
+        if !self.node(self.root)?.output_links.is_empty() {
             output.push((0, 0, 1, 1)); // no need to iterate through output links
         }
 
-        let characters: Vec<_> = string.chars().collect();
-        let mut i = 0;
-        let mut line_number = 1;
-        let mut skip_increment = false;
-        let mut line_index = 1;
+        let mut search = StreamSearch::new(self);
 
-        while i < characters.len() {
-            let c = characters[i];
+        for (i, c) in string.chars().enumerate() {
+            search.feed(&c, i, 1, c == '\n')?;
+        }
 
-            if skip_increment {
-                skip_increment = false;
-            }
-            else {
-                if c == '\n' {
-                    line_number += 1;
-                    line_index = 1;
-                }
-                else {
-                    line_index += 1;
-                }
-            }
+        output.extend(search.output.into_iter().map(|found| (found.index, found.length, found.line_number, found.line_index)));
+        Ok(output)
+    }
+}
 
-            if let Some(&next) = self.nodes.get(&node).unwrap().children.get(&c) {
-                node = next;
-                i += 1;
+#[cfg(feature = "rayon")]
+impl<T: Eq + Hash + Clone + Sync> AhoCorasick<T> {
+    /**
+     * searches one large sequence by splitting it into chunks that are searched in parallel
+     * each chunk is searched with the previous (longest pattern length - 1) symbols prepended so that matches straddling a chunk boundary are found
+     * a match is only reported by the chunk that contains its last symbol, so no match is reported twice
+     * returns the same matches as search_sequence, but not in the same order
+     */
+    pub fn search_sequence_parallel(&self, string: &[T], chunk_size: usize) -> Vec<(usize, usize)> {
+        let chunk_size = chunk_size.max(1);
 
-                for &out_node in &self.nodes.get(&node).unwrap().output_links {
-                    let len = self.nodes.get(&out_node).unwrap().length;
-                    output.push((i - len, len, line_number, line_index - len));
-                }
-            }
-            else if node == self.root {
-                i += 1;
-            }
-            else {
-                node = self.nodes.get(&node).unwrap().suffix_link.unwrap();
-                skip_increment = true;
-            }
+        if string.len() <= chunk_size {
+            return self.search_sequence(string);
         }
 
-        output
+        let overlap = self.max_pattern_length().saturating_sub(1);
+
+        string
+            .par_chunks(chunk_size)
+            .enumerate()
+            .flat_map_iter(|(chunk_index, chunk)| {
+                let chunk_start = chunk_index * chunk_size;
+                let window_start = chunk_start.saturating_sub(overlap);
+                let window_end = chunk_start + chunk.len();
+
+                self.search_sequence(&string[window_start..window_end])
+                    .into_iter()
+                    .map(move |(index, length)| (index + window_start, length))
+                    .filter(move |&(index, length)| chunk_start == 0 || index + length > chunk_start)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl AhoCorasick<char> {
+    /**
+     * searches every document in parallel
+     * returns a vector of tuples (document index, matches) in the order of the documents
+     */
+    pub fn search_many(&self, documents: &[&str]) -> Vec<(usize, Vec<(usize, usize)>)> {
+        documents
+            .par_iter()
+            .enumerate()
+            .map(|(index, document)| (index, self.search(document)))
+            .collect()
+    }
+
+    /**
+     * parallel version of search for a single large string
+     */
+    pub fn search_parallel(&self, string: &str, chunk_size: usize) -> Vec<(usize, usize)> {
+        self.search_sequence_parallel(&string.chars().collect::<Vec<char>>(), chunk_size)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -391,4 +696,203 @@ mod tests {
 
         assert_eq!(vector_quad_to_string(aho_corasick.search_verbose("st\nings")), "(3 1 2 1), (3 2 2 1)");
     }
+
+    #[test]
+    fn aho_corasick_find_tests() {
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["apple", "app", "bat"]);
+
+        assert!(aho_corasick.find("apple").is_some());
+        assert!(aho_corasick.find("appl").is_none());
+        assert!(aho_corasick.find("apples").is_none());
+        assert_eq!(aho_corasick.find("apple"), aho_corasick.find_sequence(&['a', 'p', 'p', 'l', 'e']));
+
+        aho_corasick.remove("apple");
+        assert!(aho_corasick.find("apple").is_none());
+        assert!(aho_corasick.find("app").is_some());
+    }
+
+    #[test]
+    fn aho_corasick_token_tests() {
+        let mut aho_corasick: AhoCorasick<String> = AhoCorasick::new();
+
+        let tokenize = |text: &str| text.split_whitespace().map(|word| word.to_string()).collect::<Vec<String>>();
+
+        let phrases = [tokenize("new york"), tokenize("new york city"), tokenize("york")];
+        aho_corasick.build_sequences(phrases.iter().map(|phrase| phrase.as_slice()).collect());
+
+        let mut found = aho_corasick.search_sequence(&tokenize("i love new york city"));
+        found.sort();
+        assert_eq!(vector_pair_to_string(found), "(2 2), (2 3), (3 1)");
+
+        assert_eq!(vector_pair_to_string(aho_corasick.search_sequence(&tokenize("newyork city"))), "");
+
+        aho_corasick.remove_sequence(&tokenize("new york city"));
+        let mut found = aho_corasick.search_sequence(&tokenize("new york city"));
+        found.sort();
+        assert_eq!(vector_pair_to_string(found), "(0 2), (1 1)");
+
+        //---------
+
+        let mut token_ids: AhoCorasick<u32> = AhoCorasick::new();
+        token_ids.insert_sequence(&[7, 8]);
+        token_ids.insert_sequence(&[8, 9, 10]);
+
+        let mut found = token_ids.search_sequence(&[1, 7, 8, 9, 10, 7]);
+        found.sort();
+        assert_eq!(vector_pair_to_string(found), "(1 2), (2 3)");
+
+        //---------
+
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.insert("é");
+        aho_corasick.insert("日本");
+        assert_eq!(vector_pair_to_string(aho_corasick.search("café 日本")), "(3 1), (5 2)");
+    }
+
+    #[test]
+    fn aho_corasick_callback_tests() {
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["apple", "app", "bat"]);
+
+        let mut found = Vec::new();
+        let result = aho_corasick.search_with("batapple bat", |(index, length)| {
+            found.push((index, length));
+            ControlFlow::<()>::Continue(())
+        });
+
+        assert_eq!(result, ControlFlow::Continue(()));
+        found.sort();
+        assert_eq!(vector_pair_to_string(found), "(0 3), (3 3), (3 5), (9 3)");
+
+        let mut count = 0;
+        let result = aho_corasick.search_with("bat bat bat bat", |_| {
+            count += 1;
+
+            if count == 2 {
+                ControlFlow::Break(())
+            }
+            else {
+                ControlFlow::Continue(())
+            }
+        });
+
+        assert_eq!(result, ControlFlow::Break(()));
+        assert_eq!(count, 2);
+
+        let first_long_match = aho_corasick.search_with("bat app apple", |(index, length)| {
+            if length > 3 {
+                ControlFlow::Break(index)
+            }
+            else {
+                ControlFlow::Continue(())
+            }
+        });
+
+        assert_eq!(first_long_match, ControlFlow::Break(8));
+        assert_eq!(aho_corasick.search_with("nothing", |_| ControlFlow::Break(())), ControlFlow::Continue(()));
+    }
+
+    #[test]
+    fn aho_corasick_max_pattern_length_tests() {
+        let mut aho_corasick = AhoCorasick::new();
+        assert_eq!(aho_corasick.max_pattern_length(), 0);
+
+        aho_corasick.build(vec!["apple", "bat", "grape"]);
+        assert_eq!(aho_corasick.max_pattern_length(), 5);
+
+        aho_corasick.insert("apple");
+        aho_corasick.remove("apple");
+        assert_eq!(aho_corasick.max_pattern_length(), 5);
+
+        aho_corasick.remove("grape");
+        assert_eq!(aho_corasick.max_pattern_length(), 3);
+
+        aho_corasick.remove("grape");
+        aho_corasick.insert("ba");
+        aho_corasick.remove("bat");
+        assert_eq!(aho_corasick.max_pattern_length(), 2);
+
+        aho_corasick.remove("ba");
+        assert_eq!(aho_corasick.max_pattern_length(), 0);
+    }
+
+    #[test]
+    fn aho_corasick_error_tests() {
+        let mut aho_corasick = AhoCorasick::with_limits(Limits { max_pattern_length: 4, max_nodes: 5 });
+
+        assert!(matches!(aho_corasick.try_insert("apple"), Err(Error::PatternTooLong { length: 5, limit: 4 })));
+        assert!(aho_corasick.try_insert("app").is_ok());
+        assert!(aho_corasick.try_insert("ap").is_ok());
+        assert!(matches!(aho_corasick.try_insert("bat"), Err(Error::NodeLimitExceeded { limit: 5 })));
+        assert!(aho_corasick.try_insert("appl").is_ok());
+        assert_eq!(aho_corasick.nodes.len(), 5);
+
+        aho_corasick.insert("bat");
+        assert_eq!(vector_pair_to_string(aho_corasick.search("bat")), "");
+
+        aho_corasick = AhoCorasick::with_limits(Limits { max_pattern_length: 3, ..Limits::default() });
+        assert!(matches!(aho_corasick.try_build(vec!["app", "apple", "bat"]), Err(Error::PatternTooLong { length: 5, limit: 3 })));
+        assert_eq!(vector_pair_to_string(aho_corasick.search("app")), "(0 3)");
+        assert_eq!(vector_pair_to_string(aho_corasick.search("bat")), "");
+
+        //---------
+
+        aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["apple", "pp"]);
+
+        let p_node = *aho_corasick.nodes.get(&aho_corasick.root).unwrap().children.get(&'p').unwrap();
+        aho_corasick.nodes.get_mut(&p_node).unwrap().suffix_link = None;
+        assert!(matches!(aho_corasick.try_search("ppx"), Err(Error::MissingSuffixLink(id)) if id == p_node));
+        assert_eq!(vector_pair_to_string(aho_corasick.search("ppx")), "");
+        assert!(aho_corasick.search_verbose("ppx").is_empty());
+
+        aho_corasick.nodes.get_mut(&p_node).unwrap().suffix_link = Some(aho_corasick.root);
+        let pp_node = *aho_corasick.nodes.get(&p_node).unwrap().children.get(&'p').unwrap();
+        aho_corasick.nodes.get_mut(&pp_node).unwrap().length = 10;
+        assert!(matches!(aho_corasick.try_search("pp"), Err(Error::InvalidLength(id)) if id == pp_node));
+
+        aho_corasick.nodes.remove(&pp_node);
+        assert!(matches!(aho_corasick.try_search("pp"), Err(Error::MissingNode(id)) if id == pp_node));
+        assert!(matches!(aho_corasick.try_insert("ppp"), Err(Error::MissingNode(id)) if id == pp_node));
+        assert!(aho_corasick.try_remove("pp").is_err());
+        assert_eq!(vector_pair_to_string(aho_corasick.search("pp")), "");
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn aho_corasick_parallel_tests() {
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["apple", "app", "bat", "i", "in", "tin", "sting"]);
+
+        let documents = vec!["apple", "", "batapple", "stings"];
+        let results = aho_corasick.search_many(&documents);
+
+        assert_eq!(results.len(), documents.len());
+
+        for (index, mut found) in results {
+            let mut expected = aho_corasick.search(documents[index]);
+            found.sort();
+            expected.sort();
+            assert_eq!(vector_pair_to_string(found), vector_pair_to_string(expected));
+        }
+
+        let text = "batapple stings appl e bat".repeat(20);
+
+        let mut expected = aho_corasick.search(&text);
+        expected.sort();
+
+        for chunk_size in [0, 1, 2, 3, 4, 7, 100, 10000] {
+            let mut found = aho_corasick.search_parallel(&text, chunk_size);
+            found.sort();
+            assert_eq!(vector_pair_to_string(found), vector_pair_to_string(expected.clone()));
+        }
+
+        aho_corasick.insert("");
+        let mut expected = aho_corasick.search("batapple");
+        expected.sort();
+        let mut found = aho_corasick.search_parallel("batapple", 2);
+        found.sort();
+        assert_eq!(vector_pair_to_string(found), vector_pair_to_string(expected));
+    }
 }
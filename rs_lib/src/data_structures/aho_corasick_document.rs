@@ -0,0 +1,727 @@
+use super::aho_corasick_filter::AhoCorasickFilter;
+use super::censor_strategy::CensorStrategy;
+use super::censor_strategy::escape_html;
+use super::ignore_chars::IgnoreChars;
+use crate::error::Error;
+
+/**
+ * the html elements whose content is never filtered
+ */
+const SKIPPED_ELEMENTS: [&str; 5] = ["script", "style", "pre", "code", "textarea"];
+
+/**
+ * a run of text of a document with the byte range in the document of every decoded char
+ */
+#[derive(Default)]
+struct TextRun {
+    characters: Vec<char>,
+    sources: Vec<(usize, usize)>,
+}
+
+impl TextRun {
+    fn push(&mut self, c: char, start: usize, end: usize) {
+        self.characters.push(c);
+        self.sources.push((start, end));
+    }
+}
+
+/**
+ * the output of a document where only the text runs are filtered and everything else is copied
+ */
+struct Document<'f, 's, 'd> {
+    filter: &'f AhoCorasickFilter,
+    strategy: &'s CensorStrategy<'s>,
+    source: &'d str,
+    output: String,
+    // the end of the source that was copied to the output
+    copied: usize,
+}
+
+impl<'f, 's, 'd> Document<'f, 's, 'd> {
+    fn new(filter: &'f AhoCorasickFilter, strategy: &'s CensorStrategy<'s>, source: &'d str) -> Self {
+        Self { filter, strategy, source, output: String::with_capacity(source.len()), copied: 0 }
+    }
+
+    /**
+     * filters the text run and empties it
+     */
+    fn flush<E: Fn(&str, &mut String)>(&mut self, text: &mut TextRun, escape: E) -> Result<(), Error> {
+        let (Some(&(start, _)), Some(&(_, end))) = (text.sources.first(), text.sources.last()) else {
+            return Ok(());
+        };
+
+        let sources: Vec<(usize, usize)> = text.sources.iter().map(|&(source_start, source_end)| (source_start - start, source_end - start)).collect();
+
+        if let Some(filtered) = self.filter.filter_decoded(&self.source[start..end], &text.characters, &sources, self.strategy, escape)? {
+            self.output.push_str(&self.source[self.copied..start]);
+            self.output.push_str(&filtered);
+            self.copied = end;
+        }
+
+        *text = TextRun::default();
+        Ok(())
+    }
+
+    fn finish(mut self) -> String {
+        self.output.push_str(&self.source[self.copied..]);
+        self.output
+    }
+}
+
+/**
+ * returns the index of the first closing tag of the element after the index, the length of the html if it is not closed
+ * the name has to end the tag name, ex: </codex> does not close code
+ */
+fn closing_tag(lowercase: &str, mut i: usize, name: &str) -> usize {
+    let tag = format!("</{}", name);
+
+    while let Some(close) = lowercase[i..].find(&tag) {
+        let start = i + close;
+        i = start + tag.len();
+
+        if lowercase[i..].starts_with(|c: char| c == '>' || c == '/' || c.is_ascii_whitespace()) {
+            return start;
+        }
+    }
+
+    lowercase.len()
+}
+
+/**
+ * returns the end of the tag, comment or declaration that starts at the index
+ * returns None if the < is text, ex: "a < b"
+ */
+fn html_markup_end(html: &str, index: usize) -> Option<usize> {
+    let rest = &html[index..];
+    let after = |pattern: &str, from: usize| rest[from..].find(pattern).map_or(html.len(), |end| index + from + end + pattern.len());
+
+    if rest.starts_with("<!--") {
+        return Some(after("-->", 4));
+    }
+
+    let next = rest.as_bytes().get(1).copied().unwrap_or_default();
+
+    if next == b'!' || next == b'?' {
+        return Some(after(">", 2));
+    }
+
+    let name_start = if next == b'/' { 2 } else { 1 };
+
+    if !rest.as_bytes().get(name_start).is_some_and(|byte| byte.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    // a > inside a quoted attribute value does not end the tag
+    let mut quote = None;
+
+    for (i, byte) in rest.bytes().enumerate().skip(name_start) {
+        match (quote, byte) {
+            (None, b'"' | b'\'') => quote = Some(byte),
+            (None, b'>') => return Some(index + i + 1),
+            (Some(open), _) if open == byte => quote = None,
+            _ => {}
+        }
+    }
+
+    Some(html.len())
+}
+
+/**
+ * decodes the character reference at the start of the text, ex: &amp; or &#x41;
+ * returns the char and the byte length of the reference
+ */
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let end = text.bytes().take(12).position(|byte| byte == b';')?;
+    let name = &text[1..end];
+
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => {
+            let code = match name.strip_prefix('#')? {
+                hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+                decimal => decimal.parse().ok()?,
+            };
+
+            char::from_u32(code)?
+        }
+    };
+
+    Some((c, end + 1))
+}
+
+/**
+ * escapes every ascii punctuation char with a backslash so the censored text renders as it is, ex: * becomes \*
+ */
+fn escape_markdown(text: &str, output: &mut String) {
+    for c in text.chars() {
+        if c.is_ascii_punctuation() {
+            output.push('\\');
+        }
+
+        output.push(c);
+    }
+}
+
+/**
+ * returns the char and the length of the fence run if the line opens or closes a fenced code block
+ */
+fn code_fence(line: &str) -> Option<(u8, usize)> {
+    let indent = line.bytes().take_while(|&byte| byte == b' ').count();
+
+    if indent > 3 {
+        return None;
+    }
+
+    let fence = *line.as_bytes().get(indent)?;
+    let length = line.bytes().skip(indent).take_while(|&byte| byte == fence).count();
+
+    if (fence == b'`' || fence == b'~') && length >= 3 {
+        Some((fence, length))
+    }
+    else {
+        None
+    }
+}
+
+fn is_indented_code(line: &str) -> bool {
+    (line.starts_with("    ") || line.starts_with('\t')) && !line.trim().is_empty()
+}
+
+/**
+ * returns the indent of the line in columns, a tab is 4 columns
+ */
+fn indentation(line: &str) -> usize {
+    line.bytes().map_while(|byte| match byte {
+        b' ' => Some(1),
+        b'\t' => Some(4),
+        _ => None,
+    }).sum()
+}
+
+/**
+ * removes at most columns of indent from the start of the line
+ */
+fn strip_indent(line: &str, columns: usize) -> &str {
+    let mut removed = 0;
+
+    for (i, byte) in line.bytes().enumerate() {
+        let width = match byte {
+            b' ' => 1,
+            b'\t' => 4,
+            _ => return &line[i..],
+        };
+
+        if removed + width > columns {
+            return &line[i..];
+        }
+
+        removed += width;
+    }
+
+    ""
+}
+
+/**
+ * returns the indent in columns of the content of the list item that the line starts, ex: 2 for "- item" and 4 for "10. item"
+ */
+fn list_item_indent(line: &str) -> Option<usize> {
+    let indent = line.bytes().take_while(|&byte| byte == b' ').count();
+
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = &line[indent..];
+    let marker = match rest.as_bytes().first()? {
+        b'-' | b'*' | b'+' => 1,
+        _ => {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+
+            if digits == 0 || digits > 9 || !matches!(rest.as_bytes().get(digits), Some(b'.' | b')')) {
+                return None;
+            }
+
+            digits + 1
+        }
+    };
+
+    let after = &rest[marker..];
+    let spaces = after.bytes().take_while(|&byte| byte == b' ').count();
+
+    if after.trim().is_empty() {
+        return Some(indent + marker + 1);
+    }
+
+    if spaces == 0 {
+        return None;
+    }
+
+    // the content of an item that starts with more than 4 spaces is indented code that starts after one space
+    Some(indent + marker + if spaces > 4 { 1 } else { spaces })
+}
+
+/**
+ * returns the end of the inline html that starts at the index, the html has to end before end
+ * returns None if the < is text, ex: "a < b" or "a<b bad> c"
+ * a tag is a tag name followed by attributes, an opening tag with attributes right after a letter or a digit is a comparison
+ */
+fn inline_html_end(markdown: &str, index: usize, end: usize) -> Option<usize> {
+    let rest = &markdown[index..end];
+    let after = |pattern: &str, from: usize| rest.get(from..)?.find(pattern).map(|position| index + from + position + pattern.len());
+
+    if rest.starts_with("<!--") {
+        return after("-->", 4);
+    }
+
+    if rest.starts_with("<![CDATA[") {
+        return after("]]>", 9);
+    }
+
+    if rest.starts_with("<?") {
+        return after("?>", 2);
+    }
+
+    if rest.starts_with("<!") {
+        return rest.as_bytes().get(2).filter(|byte| byte.is_ascii_alphabetic()).and_then(|_| after(">", 2));
+    }
+
+    let bytes = rest.as_bytes();
+    let is_closing = rest.starts_with("</");
+    let mut i = if is_closing { 2 } else { 1 };
+    let whitespace = |bytes: &[u8], mut i: usize| {
+        while bytes.get(i).is_some_and(|byte| byte.is_ascii_whitespace()) {
+            i += 1;
+        }
+
+        i
+    };
+
+    if !bytes.get(i).is_some_and(|byte| byte.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    while bytes.get(i).is_some_and(|&byte| byte.is_ascii_alphanumeric() || byte == b'-') {
+        i += 1;
+    }
+
+    if is_closing {
+        i = whitespace(bytes, i);
+        return (bytes.get(i) == Some(&b'>')).then_some(index + i + 1);
+    }
+
+    let mut has_attributes = false;
+
+    loop {
+        let attribute_start = whitespace(bytes, i);
+
+        if !bytes.get(attribute_start).is_some_and(|&byte| byte.is_ascii_alphabetic() || byte == b'_' || byte == b':') || attribute_start == i {
+            i = attribute_start;
+            break;
+        }
+
+        i = attribute_start;
+        has_attributes = true;
+
+        while bytes.get(i).is_some_and(|&byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.' | b':' | b'-')) {
+            i += 1;
+        }
+
+        let equals = whitespace(bytes, i);
+
+        if bytes.get(equals) != Some(&b'=') {
+            continue;
+        }
+
+        i = whitespace(bytes, equals + 1);
+
+        match bytes.get(i) {
+            Some(&quote @ (b'"' | b'\'')) => i += 1 + rest[i + 1..].find(quote as char)? + 1,
+            _ => {
+                let value_start = i;
+
+                while bytes.get(i).is_some_and(|&byte| !byte.is_ascii_whitespace() && !matches!(byte, b'"' | b'\'' | b'=' | b'<' | b'>' | b'`')) {
+                    i += 1;
+                }
+
+                if i == value_start {
+                    return None;
+                }
+            }
+        }
+    }
+
+    if bytes.get(i) == Some(&b'/') {
+        i += 1;
+    }
+
+    if bytes.get(i) != Some(&b'>') {
+        return None;
+    }
+
+    if has_attributes && markdown[..index].ends_with(|c: char| c.is_alphanumeric()) {
+        return None;
+    }
+
+    Some(index + i + 1)
+}
+
+/**
+ * returns true if the line is a link reference definition, ex: [label]: https://example.com
+ */
+fn is_link_definition(line: &str) -> bool {
+    let line = line.trim_start_matches(' ');
+
+    line.starts_with('[') && line.find("]:").is_some_and(|end| !line[1..end].contains(']'))
+}
+
+impl AhoCorasickFilter {
+    /**
+     * time: O(n)
+     * filters only the text nodes of the html
+     * tags, attributes, comments, declarations and the content of script, style, pre, code and textarea are never filtered
+     * character references are decoded before matching, ex: b&#97;d matches bad, the censored text is html escaped
+     * everything that is not censored is kept byte for byte, if the filter fails the whole html is censored
+     */
+    pub fn filter_html<'a, S: Into<CensorStrategy<'a>>>(&self, html: &str, strategy: S) -> String {
+        let strategy = strategy.into();
+        self.filter_html_with(html, &strategy).unwrap_or_else(|_| {
+            let mut output = String::new();
+            escape_html(&self.censor_all(html, &strategy, &IgnoreChars::default()), &mut output);
+            output
+        })
+    }
+
+    pub fn try_filter_html<'a, S: Into<CensorStrategy<'a>>>(&self, html: &str, strategy: S) -> Result<String, Error> {
+        self.filter_html_with(html, &strategy.into())
+    }
+
+    fn filter_html_with(&self, html: &str, strategy: &CensorStrategy) -> Result<String, Error> {
+        let mut document = Document::new(self, strategy, html);
+        let mut text = TextRun::default();
+        // ascii lowercasing keeps the byte indices
+        let lowercase = html.to_ascii_lowercase();
+        let bytes = html.as_bytes();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            if bytes[i] == b'<' {
+                if let Some(end) = html_markup_end(html, i) {
+                    document.flush(&mut text, escape_html)?;
+
+                    let name: String = lowercase[i + 1..end].chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+                    i = end;
+
+                    if SKIPPED_ELEMENTS.contains(&name.as_str()) {
+                        i = closing_tag(&lowercase, i, &name);
+                    }
+
+                    continue;
+                }
+            }
+
+            if bytes[i] == b'&' {
+                if let Some((c, length)) = decode_entity(&html[i..]) {
+                    text.push(c, i, i + length);
+                    i += length;
+                    continue;
+                }
+            }
+
+            let c = html[i..].chars().next().unwrap_or_default();
+            text.push(c, i, i + c.len_utf8());
+            i += c.len_utf8();
+        }
+
+        document.flush(&mut text, escape_html)?;
+        Ok(document.finish())
+    }
+
+    /**
+     * time: O(n)
+     * filters only the prose of the markdown
+     * fenced and indented code blocks, code spans, link and image targets, autolinks, bare urls, inline html and link reference definitions are never filtered
+     * backslash escapes are decoded before matching, the censored text is escaped so it renders as it is
+     * a line indented by 4 spaces is code unless it continues a paragraph, inside a list item the indent is counted from the content of the item
+     * everything that is not censored is kept byte for byte, if the filter fails the whole markdown is censored
+     */
+    pub fn filter_markdown<'a, S: Into<CensorStrategy<'a>>>(&self, markdown: &str, strategy: S) -> String {
+        let strategy = strategy.into();
+        self.filter_markdown_with(markdown, &strategy).unwrap_or_else(|_| {
+            let mut output = String::new();
+            escape_markdown(&self.censor_all(markdown, &strategy, &IgnoreChars::default()), &mut output);
+            output
+        })
+    }
+
+    pub fn try_filter_markdown<'a, S: Into<CensorStrategy<'a>>>(&self, markdown: &str, strategy: S) -> Result<String, Error> {
+        self.filter_markdown_with(markdown, &strategy.into())
+    }
+
+    fn filter_markdown_with(&self, markdown: &str, strategy: &CensorStrategy) -> Result<String, Error> {
+        let mut document = Document::new(self, strategy, markdown);
+        // the start of the prose lines that are not filtered yet
+        let mut prose_start: Option<usize> = None;
+        let mut fence: Option<(u8, usize)> = None;
+        let mut continues_paragraph = false;
+        // the indent of the content of the current list item
+        let mut list_indent: Option<usize> = None;
+        let mut line_start = 0;
+
+        for line in markdown.split_inclusive('\n') {
+            let start = line_start;
+            line_start += line.len();
+            let is_blank = line.trim().is_empty();
+
+            // a line that is less indented than the item content ends the list unless it continues a paragraph
+            if fence.is_none() && !is_blank && !continues_paragraph && list_indent.is_some_and(|indent| indentation(line) < indent) {
+                list_indent = None;
+            }
+
+            // the lines of a list item are classified without the indent of the item
+            let item_line = match list_indent {
+                Some(indent) if is_blank || indentation(line) >= indent => strip_indent(line, indent),
+                _ => line,
+            };
+
+            if let Some((fence_char, fence_length)) = fence {
+                if code_fence(item_line).is_some_and(|(c, length)| c == fence_char && length >= fence_length && item_line.trim_start().trim_start_matches(c as char).trim().is_empty()) {
+                    fence = None;
+                }
+
+                continue;
+            }
+
+            let is_code = code_fence(item_line).is_some() || (is_indented_code(item_line) && !continues_paragraph) || is_link_definition(item_line);
+
+            if is_code {
+                if let Some(prose) = prose_start.take() {
+                    self.filter_markdown_inline(&mut document, prose, start)?;
+                }
+
+                fence = code_fence(item_line);
+                continues_paragraph = false;
+                continue;
+            }
+
+            if let Some(indent) = list_item_indent(item_line) {
+                list_indent = Some(indentation(line) - indentation(item_line) + indent);
+            }
+
+            prose_start.get_or_insert(start);
+            continues_paragraph = !is_blank;
+        }
+
+        if let Some(prose) = prose_start {
+            self.filter_markdown_inline(&mut document, prose, markdown.len())?;
+        }
+
+        Ok(document.finish())
+    }
+
+    /**
+     * filters the inline prose between start and end, code spans, link targets, urls and inline html are skipped
+     */
+    fn filter_markdown_inline(&self, document: &mut Document, start: usize, end: usize) -> Result<(), Error> {
+        let markdown = document.source;
+        let bytes = markdown.as_bytes();
+        let mut text = TextRun::default();
+        let mut i = start;
+
+        while i < end {
+            let byte = bytes[i];
+            let next = if i + 1 < end { bytes[i + 1] } else { 0 };
+            let is_word_start = i == start || !bytes[i - 1].is_ascii_alphanumeric();
+
+            // the end of a construct that is skipped
+            let skipped = match byte {
+                b'\\' if next.is_ascii_punctuation() => {
+                    text.push(next as char, i, i + 2);
+                    i += 2;
+                    continue;
+                }
+                b'`' => {
+                    let length = bytes[i..end].iter().take_while(|&&byte| byte == b'`').count();
+                    let mut close = None;
+                    let mut j = i + length;
+
+                    while j < end {
+                        let run = bytes[j..end].iter().take_while(|&&byte| byte == b'`').count();
+
+                        if run == length {
+                            close = Some(j + run);
+                            break;
+                        }
+
+                        j += run.max(1);
+                    }
+
+                    if close.is_none() {
+                        for k in i..i + length {
+                            text.push('`', k, k + 1);
+                        }
+
+                        i += length;
+                        continue;
+                    }
+
+                    close
+                }
+                b']' if next == b'(' || next == b'[' => {
+                    text.push(']', i, i + 1);
+                    i += 1;
+
+                    let (open, close) = if next == b'(' { (b'(', b')') } else { (b'[', b']') };
+                    let mut depth = 0;
+
+                    bytes[i..end].iter().position(|&byte| {
+                        if byte == open {
+                            depth += 1;
+                        }
+                        else if byte == close {
+                            depth -= 1;
+                        }
+
+                        depth == 0
+                    }).map(|position| i + position + 1)
+                }
+                b'<' => inline_html_end(markdown, i, end),
+                b'h' | b'w' if is_word_start && ["http://", "https://", "www."].iter().any(|prefix| markdown[i..end].starts_with(prefix)) => {
+                    Some(markdown[i..end].find(|c: char| c.is_whitespace() || c == '<').map_or(end, |position| i + position))
+                }
+                _ => None,
+            };
+
+            match skipped {
+                Some(skipped_end) => {
+                    document.flush(&mut text, escape_markdown)?;
+                    i = skipped_end;
+                }
+                None => {
+                    let c = markdown[i..].chars().next().unwrap_or_default();
+                    text.push(c, i, i + c.len_utf8());
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        document.flush(&mut text, escape_markdown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter_of(words: Vec<&str>) -> AhoCorasickFilter {
+        let mut filter = AhoCorasickFilter::new();
+        filter.build(words);
+        filter
+    }
+
+    #[test]
+    fn aho_corasick_html_tests() {
+        let filter = filter_of(vec!["bad", "apple", "bad apple"]);
+
+        let html = r#"<p class="bad">a bad <b title='a > bad'>apple</b> &amp; b&#97;d</p><!-- bad --><script>var bad = 1;</script><code>bad</code> bad"#;
+        assert_eq!(filter.filter_html(html, "*"), r#"<p class="bad">a *** <b title='a > bad'>*****</b> &amp; ***</p><!-- bad --><script>var bad = 1;</script><code>bad</code> ***"#);
+
+        // only the tag with the same name closes a skipped element
+        assert_eq!(filter.filter_html("<code>x</codex> bad</code> bad", "*"), "<code>x</codex> bad</code> ***");
+        assert_eq!(filter.filter_html("<code>bad</code\n> bad <script>bad</script", "*"), "<code>bad</code\n> *** <script>bad</script");
+
+        assert_eq!(filter.filter_html("bad&nbsp;apple &unknown; bad&#x61;pple", "*"), "***&nbsp;***** &unknown; ********");
+        assert_eq!(filter.filter_html("a < bad, <!DOCTYPE html><SCRIPT>bad</SCRIPT>bad", "*"), "a < ***, <!DOCTYPE html><SCRIPT>bad</SCRIPT>***");
+        assert_eq!(filter.filter_html("<p>bad</p>", CensorStrategy::Token("<x>")), "<p>&lt;x&gt;</p>");
+        assert_eq!(filter.filter_html("<div\n  data-bad=\"bad\">\n  bad\n</div>", "*"), "<div\n  data-bad=\"bad\">\n  ***\n</div>");
+        assert_eq!(filter.filter_html("<pre>bad", "*"), "<pre>bad");
+        assert_eq!(filter.filter_html("<b>nothing</b>", "*"), "<b>nothing</b>");
+    }
+
+    #[test]
+    fn aho_corasick_markdown_tests() {
+        let filter = filter_of(vec!["bad", "apple"]);
+
+        let markdown = "\
+# bad title
+
+a bad [bad link](https://bad.example/(bad)) and `bad code` and ![bad](bad.png)
+see https://bad.example or <https://bad.example/x> or <span title=\"bad\">bad</span> bad\\_apple
+
+```bad
+bad
+```
+
+~~~~
+```
+bad
+~~~~
+
+    bad indented
+
+text
+    bad continued
+
+- `` a ` bad `` and `bad
+[bad]: https://bad.example
+[bad][bad] bad";
+
+        let expected = "\
+# xxx title
+
+a xxx [xxx link](https://bad.example/(bad)) and `bad code` and ![xxx](bad.png)
+see https://bad.example or <https://bad.example/x> or <span title=\"bad\">xxx</span> xxx\\_xxxxx
+
+```bad
+bad
+```
+
+~~~~
+```
+bad
+~~~~
+
+    bad indented
+
+text
+    xxx continued
+
+- `` a ` bad `` and `xxx
+[bad]: https://bad.example
+[xxx][bad] xxx";
+
+        assert_eq!(filter.filter_markdown(markdown, "x"), expected);
+        assert_eq!(filter.filter_markdown("a bad day", "*"), "a \\*\\*\\* day");
+        assert_eq!(filter.filter_markdown("wwwbad http bad", "*"), "www\\*\\*\\* http \\*\\*\\*");
+        assert_eq!(filter.filter_markdown("", "*"), "");
+    }
+
+    #[test]
+    fn aho_corasick_markdown_list_tests() {
+        let filter = filter_of(vec!["bad"]);
+
+        // the paragraphs of a list item are indented like its content, its code is indented by 4 more spaces
+        let markdown = "- a bad item\n\n    a bad paragraph\n\n      bad code\n\n  1. nested bad\n\n         bad code\n\n     nested bad paragraph\n\nbad after\n\n    bad code";
+        let expected = "- a xxx item\n\n    a xxx paragraph\n\n      bad code\n\n  1. nested xxx\n\n         bad code\n\n     nested xxx paragraph\n\nxxx after\n\n    bad code";
+        assert_eq!(filter.filter_markdown(markdown, "x"), expected);
+
+        assert_eq!(filter.filter_markdown("10. bad\n\n    bad\n- bad\n  ```\n  bad\n  ```\n  bad", "x"), "10. xxx\n\n    xxx\n- xxx\n  ```\n  bad\n  ```\n  xxx");
+        assert_eq!(filter.filter_markdown("---\n\n    bad", "x"), "---\n\n    bad");
+    }
+
+    #[test]
+    fn aho_corasick_markdown_inline_html_tests() {
+        let filter = filter_of(vec!["bad"]);
+
+        // only a tag name followed by attributes is a tag
+        assert_eq!(filter.filter_markdown("a<b bad> c", "x"), "a<b xxx> c");
+        assert_eq!(filter.filter_markdown("a <b bad> c", "x"), "a <b bad> c");
+        assert_eq!(filter.filter_markdown("x<sup>bad</sup> <a href=\"/bad\" title='bad' data-x=bad>bad</a>", "x"), "x<sup>xxx</sup> <a href=\"/bad\" title='bad' data-x=bad>xxx</a>");
+        assert_eq!(filter.filter_markdown("<1 bad> <b =bad> <b x=> bad</b x> <b\nbad/>", "x"), "<1 xxx> <b =xxx> <b x=> xxx</b x> <b\nbad/>");
+        assert_eq!(filter.filter_markdown("<!-- bad --> <?bad?> <!DOCTYPE bad> <![CDATA[bad]]> <!-- bad", "x"), "<!-- bad --> <?bad?> <!DOCTYPE bad> <![CDATA[bad]]> <!-- xxx");
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+use super::aho_corasick::AhoCorasick;
+use crate::error::Error;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/**
+ * how bytes that are not valid utf-8 are handled when searching files
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidUtf8 {
+    /// every invalid sequence is decoded as U+FFFD and never matches a pattern unless the pattern contains U+FFFD
+    #[default]
+    Lossy,
+    /// the patterns are converted to their utf-8 bytes and the file is searched byte by byte
+    Bytes,
+}
+
+/**
+ * a match in a file
+ * index and length are in bytes of the file
+ * line number and line index start at 1, the line index is the byte column of the match start
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMatch {
+    pub index: usize,
+    pub length: usize,
+    pub line_number: usize,
+    pub line_index: usize,
+}
+
+#[derive(Clone, Copy)]
+struct SymbolPosition {
+    index: usize,
+    line_number: usize,
+    line_start: usize,
+}
+
+/**
+ * incremental search that only remembers the positions of the last (longest pattern length) symbols
+ */
+pub(crate) struct StreamSearch<'a, T> {
+    automaton: &'a AhoCorasick<T>,
+    node: usize,
+    history: VecDeque<SymbolPosition>,
+    capacity: usize,
+    line_number: usize,
+    line_start: usize,
+    pub(crate) output: Vec<FileMatch>,
+}
+
+impl<'a, T: Eq + Hash + Clone> StreamSearch<'a, T> {
+    pub(crate) fn new(automaton: &'a AhoCorasick<T>) -> Self {
+        let capacity = automaton.max_pattern_length().max(1);
+
+        Self {
+            automaton,
+            node: automaton.root,
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            line_number: 1,
+            line_start: 0,
+            output: Vec::new(),
+        }
+    }
+
+    /**
+     * index and length are the position of the symbol in the units of the output, ex: bytes of a file or chars of a string
+     */
+    pub(crate) fn feed(&mut self, symbol: &T, index: usize, length: usize, is_new_line: bool) -> Result<(), Error> {
+        loop {
+            if let Some(&next) = self.automaton.node(self.node)?.children.get(symbol) {
+                self.node = next;
+                break;
+            }
+            else if self.node == self.automaton.root {
+                break;
+            }
+            else {
+                self.node = self.automaton.suffix_link(self.node)?;
+            }
+        }
+
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(SymbolPosition { index, line_number: self.line_number, line_start: self.line_start });
+
+        if self.node != self.automaton.root {
+            for &out_node in &self.automaton.node(self.node)?.output_links {
+                let pattern_length = self.automaton.node(out_node)?.length;
+
+                // the empty pattern is not reported because it has no position in a file
+                if pattern_length == 0 {
+                    continue;
+                }
+
+                let history_index = self.history.len().checked_sub(pattern_length).ok_or(Error::InvalidLength(out_node))?;
+                let start = self.history[history_index];
+
+                self.output.push(FileMatch {
+                    index: start.index,
+                    length: index + length - start.index,
+                    line_number: start.line_number,
+                    line_index: start.index - start.line_start + 1,
+                });
+            }
+        }
+
+        if is_new_line {
+            self.line_number += 1;
+            self.line_start = index + length;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * decodes utf-8 that arrives in chunks
+ * a sequence that is cut at the end of a chunk is kept until the next chunk
+ * every invalid sequence is decoded as U+FFFD
+ */
+#[derive(Default)]
+pub(crate) struct LossyDecoder {
+    pending: Vec<u8>,
+    index: usize,
+}
+
+impl LossyDecoder {
+    /**
+     * calls decoded with every char, its byte index in the whole input and its length in bytes
+     */
+    pub(crate) fn feed<F: FnMut(char, usize, usize) -> Result<(), Error>>(&mut self, chunk: &[u8], is_last: bool, mut decoded: F) -> Result<(), Error> {
+        let mut buffer = std::mem::take(&mut self.pending);
+        buffer.extend_from_slice(chunk);
+        let mut rest = buffer.as_slice();
+
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    self.feed_str(valid, &mut decoded)?;
+                    return Ok(());
+                }
+                Err(error) => {
+                    let (valid, invalid) = rest.split_at(error.valid_up_to());
+
+                    if let Ok(valid) = std::str::from_utf8(valid) {
+                        self.feed_str(valid, &mut decoded)?;
+                    }
+
+                    match error.error_len() {
+                        Some(length) => {
+                            self.feed_invalid(length, &mut decoded)?;
+                            rest = &invalid[length..];
+                        }
+                        None if is_last => {
+                            self.feed_invalid(invalid.len(), &mut decoded)?;
+                            return Ok(());
+                        }
+                        None => {
+                            self.pending = invalid.to_vec();
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn feed_str<F: FnMut(char, usize, usize) -> Result<(), Error>>(&mut self, valid: &str, decoded: &mut F) -> Result<(), Error> {
+        for (offset, c) in valid.char_indices() {
+            decoded(c, self.index + offset, c.len_utf8())?;
+        }
+
+        self.index += valid.len();
+        Ok(())
+    }
+
+    fn feed_invalid<F: FnMut(char, usize, usize) -> Result<(), Error>>(&mut self, length: usize, decoded: &mut F) -> Result<(), Error> {
+        decoded(char::REPLACEMENT_CHARACTER, self.index, length)?;
+        self.index += length;
+        Ok(())
+    }
+}
+
+impl AhoCorasick<char> {
+    /**
+     * returns the utf-8 bytes of every non empty pattern
+     */
+    fn byte_patterns(&self) -> Result<Vec<Vec<u8>>, Error> {
+        let mut patterns = Vec::new();
+        let mut stack = vec![(self.root, String::new())];
+
+        while let Some((node_id, prefix)) = stack.pop() {
+            let node = self.node(node_id)?;
+
+            if node.length != 0 {
+                patterns.push(prefix.clone().into_bytes());
+            }
+
+            for (&c, &child) in &node.children {
+                let mut child_prefix = prefix.clone();
+                child_prefix.push(c);
+                stack.push((child, child_prefix));
+            }
+        }
+
+        Ok(patterns)
+    }
+
+    fn byte_automaton(&self) -> Result<AhoCorasick<u8>, Error> {
+        let patterns = self.byte_patterns()?;
+        let mut automaton = AhoCorasick::new();
+        automaton.try_build_sequences(patterns.iter().map(|pattern| pattern.as_slice()).collect())?;
+
+        Ok(automaton)
+    }
+
+    /**
+     * searches bytes that do not have to be valid utf-8
+     * returns the matches with byte offsets and line numbers
+     */
+    pub fn search_bytes(&self, bytes: &[u8], invalid_utf8: InvalidUtf8) -> Result<Vec<FileMatch>, Error> {
+        match invalid_utf8 {
+            InvalidUtf8::Lossy => {
+                let mut search = StreamSearch::new(self);
+                LossyDecoder::default().feed(bytes, true, |c, index, length| search.feed(&c, index, length, c == '\n'))?;
+
+                Ok(search.output)
+            }
+            InvalidUtf8::Bytes => {
+                let automaton = self.byte_automaton()?;
+                let mut search = StreamSearch::new(&automaton);
+
+                for (index, byte) in bytes.iter().enumerate() {
+                    search.feed(byte, index, 1, *byte == b'\n')?;
+                }
+
+                Ok(search.output)
+            }
+        }
+    }
+
+    /**
+     * searches a reader chunk by chunk, only the positions of the last (longest pattern length) symbols are kept in memory
+     * returns the matches with byte offsets and line numbers
+     */
+    pub fn search_reader<R: Read>(&self, reader: R, invalid_utf8: InvalidUtf8) -> Result<Vec<FileMatch>, Error> {
+        let mut reader = BufReader::with_capacity(BUFFER_SIZE, reader);
+
+        match invalid_utf8 {
+            InvalidUtf8::Lossy => {
+                let mut search = StreamSearch::new(self);
+                let mut decoder = LossyDecoder::default();
+
+                loop {
+                    let chunk = reader.fill_buf()?;
+                    let length = chunk.len();
+                    decoder.feed(chunk, length == 0, |c, index, length| search.feed(&c, index, length, c == '\n'))?;
+
+                    if length == 0 {
+                        return Ok(search.output);
+                    }
+
+                    reader.consume(length);
+                }
+            }
+            InvalidUtf8::Bytes => {
+                let automaton = self.byte_automaton()?;
+                let mut search = StreamSearch::new(&automaton);
+                let mut index = 0;
+
+                loop {
+                    let chunk = reader.fill_buf()?;
+                    let length = chunk.len();
+
+                    if length == 0 {
+                        return Ok(search.output);
+                    }
+
+                    for byte in chunk {
+                        search.feed(byte, index, 1, *byte == b'\n')?;
+                        index += 1;
+                    }
+
+                    reader.consume(length);
+                }
+            }
+        }
+    }
+
+    /**
+     * searches a file with lossy utf-8 decoding
+     * returns the matches with byte offsets and line numbers
+     */
+    pub fn search_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<FileMatch>, Error> {
+        self.search_file_with(path, InvalidUtf8::Lossy)
+    }
+
+    /**
+     * memory maps the file when the mmap feature is enabled and falls back to buffered reads when mapping fails or the feature is disabled
+     */
+    pub fn search_file_with<P: AsRef<Path>>(&self, path: P, invalid_utf8: InvalidUtf8) -> Result<Vec<FileMatch>, Error> {
+        let file = File::open(path)?;
+
+        #[cfg(feature = "mmap")]
+        {
+            // the map is only read while searching, modifying the file at the same time is undefined behavior that memmap2 can not prevent
+            if let Ok(map) = unsafe { memmap2::Mmap::map(&file) } {
+                return self.search_bytes(&map, invalid_utf8);
+            }
+        }
+
+        self.search_reader(file, invalid_utf8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn file_matches_to_string(mut matches: Vec<FileMatch>) -> String {
+        matches.sort_by_key(|found| (found.index, found.length));
+
+        matches
+            .iter()
+            .map(|found| format!("({} {} {} {})", found.index, found.length, found.line_number, found.line_index))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /**
+     * returns one byte per read to split every multi byte sequence
+     */
+    struct SlowReader<'a> {
+        bytes: &'a [u8],
+    }
+
+    impl Read for SlowReader<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            if self.bytes.is_empty() || buffer.is_empty() {
+                return Ok(0);
+            }
+
+            buffer[0] = self.bytes[0];
+            self.bytes = &self.bytes[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn aho_corasick_file_tests() {
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["apple", "app", "bat", "café", "日本"]);
+
+        let text = "bat\napple café\n\n日本 app".as_bytes();
+        let expected = "(0 3 1 1), (4 3 2 1), (4 5 2 1), (10 5 2 7), (17 6 4 1), (24 3 4 8)";
+
+        assert_eq!(file_matches_to_string(aho_corasick.search_bytes(text, InvalidUtf8::Lossy).unwrap()), expected);
+        assert_eq!(file_matches_to_string(aho_corasick.search_bytes(text, InvalidUtf8::Bytes).unwrap()), expected);
+        assert_eq!(file_matches_to_string(aho_corasick.search_reader(SlowReader { bytes: text }, InvalidUtf8::Lossy).unwrap()), expected);
+        assert_eq!(file_matches_to_string(aho_corasick.search_reader(SlowReader { bytes: text }, InvalidUtf8::Bytes).unwrap()), expected);
+
+        //---------
+
+        let invalid = b"ap\xffp app\n\xe6\x97bat \xe6\x97\xa5\xe6\x9c\xac\xe6";
+        let expected = "(5 3 1 6), (11 3 2 3), (15 6 2 7)";
+
+        assert_eq!(file_matches_to_string(aho_corasick.search_bytes(invalid, InvalidUtf8::Lossy).unwrap()), expected);
+        assert_eq!(file_matches_to_string(aho_corasick.search_bytes(invalid, InvalidUtf8::Bytes).unwrap()), expected);
+        assert_eq!(file_matches_to_string(aho_corasick.search_reader(SlowReader { bytes: invalid }, InvalidUtf8::Lossy).unwrap()), expected);
+
+        aho_corasick.insert("p\u{fffd}p");
+        assert_eq!(file_matches_to_string(aho_corasick.search_bytes(b"ap\xffp", InvalidUtf8::Lossy).unwrap()), "(1 3 1 2)");
+        assert_eq!(file_matches_to_string(aho_corasick.search_bytes(b"ap\xffp", InvalidUtf8::Bytes).unwrap()), "");
+
+        //---------
+
+        let path = std::env::temp_dir().join(format!("rs_lib_aho_corasick_file_test_{}.txt", std::process::id()));
+        File::create(&path).unwrap().write_all(text).unwrap();
+
+        let expected = "(0 3 1 1), (4 3 2 1), (4 5 2 1), (10 5 2 7), (17 6 4 1), (24 3 4 8)";
+        assert_eq!(file_matches_to_string(aho_corasick.search_file(&path).unwrap()), expected);
+        assert_eq!(file_matches_to_string(aho_corasick.search_file_with(&path, InvalidUtf8::Bytes).unwrap()), expected);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(aho_corasick.search_file(&path), Err(Error::Io(_))));
+
+        //---------
+
+        // search_verbose reports the same lines as the stream search, a match is on the line where it starts
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["t\nap", "app"]);
+
+        let text = "bat\napp\n app";
+        let expected = "(2 4 1 3), (4 3 2 1), (9 3 3 2)";
+        assert_eq!(file_matches_to_string(aho_corasick.search_bytes(text.as_bytes(), InvalidUtf8::Lossy).unwrap()), expected);
+
+        let verbose = aho_corasick.search_verbose(text).into_iter().map(|(index, length, line_number, line_index)| format!("({} {} {} {})", index, length, line_number, line_index));
+        assert_eq!(verbose.collect::<Vec<_>>().join(", "), expected);
+    }
+}
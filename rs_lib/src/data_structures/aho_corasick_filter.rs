@@ -1,228 +1,1382 @@
 use std::cmp::max;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
 use super::aho_corasick::AhoCorasick;
+use super::aho_corasick::Limits;
+use super::aho_corasick_file::LossyDecoder;
+use super::censor_strategy::CensorStrategy;
+use super::censor_strategy::Markup;
+use super::filter_term::CensorSpan;
+use super::filter_term::ExplainedSpan;
+use super::filter_term::FilterExplanation;
+use super::filter_term::FilterReport;
+use super::filter_term::Score;
+use super::filter_term::ScoreOptions;
+use super::filter_term::Severity;
+use super::filter_term::TermHit;
+use super::filter_term::TermInfo;
+use super::filter_term::check_weight;
+use super::ignore_chars::IgnoreChars;
+use super::ignore_chars::is_default_ignorable;
+use super::normalization::CanonicalOrder;
+use super::normalization::Normalization;
+use super::normalization::NormalizedText;
+use crate::error::Error;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/**
+ * one node the text can be in, a char with equivalents can lead to several nodes
+ */
+struct Thread {
+    node: usize,
+    // the pattern char of the edge into the node
+    last_symbol: Option<char>,
+    // char indices of the last matched chars, a match of length n starts at the nth last index
+    // ignored chars are not in the history so matches can contain ignored chars
+    history: VecDeque<usize>,
+    // the matches of the node end at the current char
+    is_reported: bool,
+}
+
+/**
+ * positions of one automaton while walking a text, no thread means the walk is at the root
+ */
+struct WalkState {
+    threads: Vec<Thread>,
+    capacity: usize,
+}
+
+impl WalkState {
+    fn new(automaton: &AhoCorasick) -> Self {
+        Self { threads: Vec::new(), capacity: automaton.max_pattern_length().max(1) }
+    }
+
+    /**
+     * forgets the matches in progress
+     */
+    fn reset(&mut self) {
+        self.threads.clear();
+    }
+
+    /**
+     * returns the smallest index where a match in progress can start, None if no match is in progress
+     */
+    fn pending_start(&self) -> Option<usize> {
+        self.threads.iter().filter_map(|thread| thread.history.front().copied()).min()
+    }
+
+    /**
+     * adds the thread, a thread in the same node as another one is merged into it and the earlier history is kept
+     */
+    fn push(&mut self, thread: Thread) {
+        let Some(existing) = self.threads.iter_mut().find(|existing| existing.node == thread.node) else {
+            self.threads.push(thread);
+            return;
+        };
+
+        let is_reported = existing.is_reported || thread.is_reported;
+
+        if thread.history.front() < existing.history.front() {
+            *existing = thread;
+        }
+
+        existing.is_reported = is_reported;
+    }
+}
+
+/**
+ * a match of a pattern, start and end are char indices and end is exclusive
+ */
+struct FoundMatch {
+    start: usize,
+    end: usize,
+    node: usize,
+}
+
+/**
+ * how the case of the text and the patterns is compared
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseMatching {
+    #[default]
+    Sensitive,
+    /// ascii letters match regardless of case
+    AsciiInsensitive,
+    /// chars match regardless of case when their lowercase or uppercase is a single char, ex: 'Ё' matches 'ё' but 'ß' does not match "SS"
+    Insensitive,
+}
+
+/**
+ * finds the matches of a text that is fed char by char
+ * the indices are char indices of the whole text so a text can be fed in parts
+ */
+struct Matcher<'f, 'i> {
+    filter: &'f AhoCorasickFilter,
+    ignore_chars: &'f IgnoreChars<'i>,
+    blocked: WalkState,
+    allowed: WalkState,
+    has_allowlist: bool,
+    // number of ignored chars since the last char that was not ignored
+    gap: usize,
+    matches: Vec<FoundMatch>,
+    // a match is reported again for every char of a collapsed run, only the longest range of the match is kept
+    match_indices: HashMap<(usize, usize), usize>,
+    // (start, end) of every allowlist match that can still contain a match
+    allowed_matches: Vec<(usize, usize)>,
+    // the combining marks that wait for the next starter to be fed in canonical order
+    order: CanonicalOrder,
+    // the largest index that was fed, the reordered marks are fed with it so the indices never decrease
+    last_index: usize,
+}
+
+impl<'f, 'i> Matcher<'f, 'i> {
+    fn new(filter: &'f AhoCorasickFilter, ignore_chars: &'f IgnoreChars<'i>) -> Self {
+        Self {
+            filter,
+            ignore_chars,
+            blocked: WalkState::new(&filter.inner),
+            allowed: WalkState::new(&filter.allowlist),
+            // the automata cache their longest pattern, creating a matcher is O(1) whatever the size of the dictionaries
+            has_allowlist: filter.allowlist.max_pattern_length() != 0,
+            gap: 0,
+            matches: Vec::new(),
+            match_indices: HashMap::new(),
+            allowed_matches: Vec::new(),
+            order: CanonicalOrder::default(),
+            last_index: 0,
+        }
+    }
+
+    /**
+     * feeds the normalized chars of the char at index i of the text
+     * every normalized char has the index of the original char so the matches are ranges of the original text
+     */
+    fn feed(&mut self, i: usize, c: char) -> Result<(), Error> {
+        let normalization = self.filter.normalization;
+
+        if normalization.is_identity() {
+            return self.feed_normalized(i, c);
+        }
+
+        let mut normalized = Vec::new();
+        normalization.apply(c, |normalized_char| normalized.push(normalized_char));
+
+        if normalized.is_empty() {
+            // a char that is removed by the normalization belongs to the matches that end before it, ex: a stripped combining mark
+            for found in self.matches.iter_mut().filter(|found| found.end == i) {
+                found.end = i + 1;
+            }
+
+            for allowed in self.allowed_matches.iter_mut().filter(|allowed| allowed.1 == i) {
+                allowed.1 = i + 1;
+            }
+        }
+
+        let mut ordered = Vec::new();
+
+        for normalized_char in normalized {
+            self.order.push(normalized_char, i, |c, origin| ordered.push((c, origin)));
+        }
+
+        self.feed_ordered(ordered)
+    }
+
+    /**
+     * feeds the combining marks that wait for a starter, it is called after the last char of the text
+     */
+    fn finish(&mut self) -> Result<(), Error> {
+        let mut ordered = Vec::new();
+        self.order.flush(|c, origin| ordered.push((c, origin)));
+        self.feed_ordered(ordered)
+    }
+
+    fn feed_ordered(&mut self, ordered: Vec<(char, usize)>) -> Result<(), Error> {
+        for (c, origin) in ordered {
+            // a reordered mark can come from a char before the previous mark, a match that ends on it covers both
+            self.last_index = max(self.last_index, origin);
+            self.feed_normalized(self.last_index, c)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * a gap of ignored chars longer than the max gap ends every match in progress
+     */
+    fn feed_normalized(&mut self, i: usize, c: char) -> Result<(), Error> {
+        let filter = self.filter;
+
+        // an invisible char only continues the matches that contain it
+        let is_invisible = filter.skip_invisible && is_default_ignorable(c);
+
+        if !is_invisible {
+            if self.ignore_chars.is_ignored(c) {
+                self.gap += 1;
+                return Ok(());
+            }
+
+            if self.ignore_chars.gap_limit().is_some_and(|max_gap| self.gap > max_gap) {
+                self.blocked.reset();
+                self.allowed.reset();
+            }
+
+            self.gap = 0;
+        }
+
+        let matches = &mut self.matches;
+        let match_indices = &mut self.match_indices;
+
+        filter.step(&filter.inner, &mut self.blocked, i, c, is_invisible, |start, end, node| {
+            if let Some(&index) = match_indices.get(&(start, node)) {
+                matches[index].end = end;
+            }
+            else {
+                match_indices.insert((start, node), matches.len());
+                matches.push(FoundMatch { start, end, node });
+            }
+        })?;
+
+        if self.has_allowlist {
+            let allowed_matches = &mut self.allowed_matches;
+            filter.step(&filter.allowlist, &mut self.allowed, i, c, is_invisible, |start, end, _| allowed_matches.push((start, end)))?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * returns the smallest index where a match that is not found yet can start
+     * next is the index of the next char that is fed
+     */
+    fn pending_start(&self, next: usize) -> usize {
+        let blocked = self.blocked.pending_start().unwrap_or(next);
+        let allowed = self.allowed.pending_start().unwrap_or(next);
+        let marks = self.order.first_origin().unwrap_or(next);
+
+        blocked.min(allowed).min(marks)
+    }
+
+    /**
+     * removes the matches that start before the limit and returns the ones that are not inside an allowlist match sorted by start
+     * the limit can not be larger than pending_start so the matches before it are final
+     */
+    fn take_matches(&mut self, limit: usize) -> Vec<FoundMatch> {
+        self.allowed_matches.sort_unstable();
+
+        // furthest_ends[k] is the end of the furthest allowlist match of the first k + 1 allowlist matches
+        let mut furthest_ends: Vec<usize> = Vec::with_capacity(self.allowed_matches.len());
+
+        for &(_, end) in &self.allowed_matches {
+            furthest_ends.push(max(end, furthest_ends.last().copied().unwrap_or(0)));
+        }
+
+        let (mut taken, kept): (Vec<FoundMatch>, Vec<FoundMatch>) = std::mem::take(&mut self.matches).into_iter().partition(|found| found.start < limit);
+
+        taken.retain(|found| {
+            let count = self.allowed_matches.partition_point(|&(start, _)| start <= found.start);
+            count == 0 || furthest_ends[count - 1] < found.end
+        });
+        taken.sort_by_key(|found| (found.start, found.end));
+
+        // the matches that are not found yet start at or after the limit so they can only be inside the allowlist matches that end after the limit
+        self.allowed_matches.retain(|&(_, end)| end > limit);
+        self.match_indices = kept.iter().enumerate().map(|(index, found)| ((found.start, found.node), index)).collect();
+        self.matches = kept;
+
+        taken
+    }
+}
+
+/**
+ * the part of a stream that is not written yet
+ */
+struct StreamState<'f, 'i> {
+    matcher: Matcher<'f, 'i>,
+    // the chars that are not written yet, pending[0] is the char at index base
+    pending: Vec<char>,
+    base: usize,
+    // the last merged range, it can still grow while new matches overlap it
+    open_range: Option<(usize, usize)>,
+}
+
+impl<'f, 'i> StreamState<'f, 'i> {
+    fn new(filter: &'f AhoCorasickFilter, ignore_chars: &'f IgnoreChars<'i>) -> Self {
+        Self { matcher: Matcher::new(filter, ignore_chars), pending: Vec::new(), base: 0, open_range: None }
+    }
+
+    fn push(&mut self, c: char) -> Result<(), Error> {
+        self.matcher.feed(self.base + self.pending.len(), c)?;
+        self.pending.push(c);
+        Ok(())
+    }
+
+    /**
+     * feeds the combining marks that wait for a starter, it is called before the last release
+     */
+    fn finish(&mut self) -> Result<(), Error> {
+        self.matcher.finish()
+    }
+
+    /**
+     * censors and removes the pending chars that can not be part of a match that is not found yet
+     * every char is released when is_last is true
+     */
+    fn release(&mut self, strategy: &CensorStrategy, is_last: bool) -> String {
+        let next = self.base + self.pending.len();
+        let limit = if is_last { next } else { self.matcher.pending_start(next) };
+        let found = self.matcher.take_matches(limit);
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+        for (start, end) in self.open_range.take().into_iter().chain(found.iter().map(|found| (found.start, found.end))) {
+            match ranges.last_mut() {
+                Some(last) if start < last.1 => last.1 = max(last.1, end),
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        // a match that is not found yet can overlap the last range if the range ends after the limit
+        let mut released = limit;
+
+        if ranges.last().is_some_and(|&(_, end)| end > limit) {
+            self.open_range = ranges.pop();
+            released = self.open_range.map_or(limit, |(start, _)| start);
+        }
+
+        let ranges: Vec<(usize, usize)> = ranges.iter().map(|&(start, end)| (start - self.base, end - self.base)).collect();
+        let output = self.matcher.filter.censor(&self.pending[..released - self.base], &ranges, strategy, self.matcher.ignore_chars);
+
+        self.pending.drain(..released - self.base);
+        self.base = released;
+
+        output
+    }
+}
 
 pub struct AhoCorasickFilter {
-    inner: AhoCorasick
+    inner: AhoCorasick,
+    // the word and tags of every pattern node of inner
+    terms: HashMap<usize, (String, TermInfo)>,
+    allowlist: AhoCorasick,
+    // the allowed words before normalization
+    allowed_words: HashSet<String>,
+    normalization: Normalization,
+    equivalences: HashMap<char, Vec<char>>,
+    collapse_repeats: bool,
+    case_matching: CaseMatching,
+    skip_invisible: bool,
+    strip_invisible: bool
+}
+
+impl Default for AhoCorasickFilter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl AhoCorasickFilter {
-    pub fn new() -> Self {
-        Self { inner: AhoCorasick::new() }
+impl AhoCorasickFilter {
+    pub fn new() -> Self {
+        Self::with_limits(Limits::default())
+    }
+
+    /**
+     * like AhoCorasick the functions without try_ skip a word that exceeds the limits, use the try_ functions to get the error
+     */
+    pub fn with_limits(limits: Limits) -> Self {
+        Self {
+            inner: AhoCorasick::with_limits(limits),
+            terms: HashMap::new(),
+            allowlist: AhoCorasick::with_limits(limits),
+            allowed_words: HashSet::new(),
+            normalization: Normalization::default(),
+            equivalences: HashMap::new(),
+            collapse_repeats: false,
+            case_matching: CaseMatching::Sensitive,
+            skip_invisible: true,
+            strip_invisible: false
+        }
+    }
+
+    /**
+     * when enabled a run of the same char in the text matches a single char of a pattern, ex: "aappplee" matches "apple"
+     * a doubled char in a pattern matches a run of 2 or more chars
+     * the censored range covers the whole run
+     */
+    pub fn set_collapse_repeats(&mut self, collapse_repeats: bool) {
+        self.collapse_repeats = collapse_repeats;
+    }
+
+    /**
+     * the text is matched with the case matching but the output keeps the original chars of the text
+     * the patterns do not have to be lowercase, ex: "Apple" matches "aPPLE" when case insensitive
+     * the words and the text are folded to one case so the words that are already inserted are inserted again
+     * if a word can not be inserted again the filter is left unchanged
+     */
+    pub fn set_case_matching(&mut self, case_matching: CaseMatching) {
+        let _ = self.try_set_case_matching(case_matching);
+    }
+
+    pub fn try_set_case_matching(&mut self, case_matching: CaseMatching) -> Result<(), Error> {
+        let previous = std::mem::replace(&mut self.case_matching, case_matching);
+        self.reinsert().inspect_err(|_| self.case_matching = previous)
+    }
+
+    /**
+     * enabled by default
+     * when enabled the default ignorable chars (zero width space, zero width joiner, soft hyphen, bidi controls, ...) are skipped when matching
+     * an invisible char is still matched when the match in progress continues with it, ex: the zero width joiners of an emoji sequence
+     * unlike ignored chars they are censored inside a match and do not count towards the max gap of IgnoreChars
+     */
+    pub fn set_skip_invisible(&mut self, skip_invisible: bool) {
+        self.skip_invisible = skip_invisible;
+    }
+
+    /**
+     * when enabled the filter functions remove the default ignorable chars from the output
+     */
+    pub fn set_strip_invisible(&mut self, strip_invisible: bool) {
+        self.strip_invisible = strip_invisible;
+    }
+
+    /**
+     * a blocked match that lies entirely inside a match of an allowed word is not censored, ex: "class" allows "ass"
+     * allowed words are matched with the same settings as blocked words
+     */
+    pub fn insert_allowed(&mut self, word: &str) {
+        let _ = self.try_insert_allowed(word);
+    }
+
+    pub fn try_insert_allowed(&mut self, word: &str) -> Result<(), Error> {
+        self.allowlist.try_insert(&self.pattern(word))?;
+        self.allowed_words.insert(word.to_string());
+        Ok(())
+    }
+
+    pub fn remove_allowed(&mut self, word: &str) {
+        let _ = self.try_remove_allowed(word);
+    }
+
+    pub fn try_remove_allowed(&mut self, word: &str) -> Result<(), Error> {
+        self.allowlist.try_remove(&self.pattern(word))?;
+        self.allowed_words.remove(word);
+        Ok(())
+    }
+
+    pub fn build_allowed(&mut self, words: Vec<&str>) {
+        let _ = self.try_build_allowed(words);
+    }
+
+    pub fn try_build_allowed(&mut self, words: Vec<&str>) -> Result<(), Error> {
+        let patterns: Vec<String> = words.iter().map(|word| self.pattern(word)).collect();
+        let result = self.allowlist.try_build(patterns.iter().map(|pattern| pattern.as_str()).collect());
+
+        // the links are constructed once for all the words, the words inserted before a failure are allowed
+        for (word, pattern) in words.into_iter().zip(&patterns) {
+            if self.allowlist.find(pattern).is_some() {
+                self.allowed_words.insert(word.to_string());
+            }
+        }
+
+        result
+    }
+
+    /**
+     * the text and the words are normalized before matching, the censoring still replaces the original chars of the text
+     * the words that are already inserted are normalized again, if a word can not be inserted again the filter is left unchanged
+     */
+    pub fn set_normalization(&mut self, normalization: Normalization) {
+        let _ = self.try_set_normalization(normalization);
+    }
+
+    pub fn try_set_normalization(&mut self, normalization: Normalization) -> Result<(), Error> {
+        let previous = std::mem::replace(&mut self.normalization, normalization);
+        self.reinsert().inspect_err(|_| self.normalization = previous)
+    }
+
+    /**
+     * inserts the words again after the patterns of the words changed
+     * on error the automatons and the words are restored
+     */
+    fn reinsert(&mut self) -> Result<(), Error> {
+        let (limits, allowlist_limits) = (self.inner.limits(), self.allowlist.limits());
+        let inner = std::mem::replace(&mut self.inner, AhoCorasick::with_limits(limits));
+        let allowlist = std::mem::replace(&mut self.allowlist, AhoCorasick::with_limits(allowlist_limits));
+        let terms = std::mem::take(&mut self.terms);
+        let allowed_words = std::mem::take(&mut self.allowed_words);
+
+        // sorted so the word that is kept for words that normalize to the same pattern does not depend on the hash order
+        let mut sorted_terms: Vec<&(String, TermInfo)> = terms.values().collect();
+        sorted_terms.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut sorted_allowed: Vec<&String> = allowed_words.iter().collect();
+        sorted_allowed.sort();
+
+        let result = sorted_terms
+            .into_iter()
+            .try_for_each(|(word, info)| self.try_insert_with(word, *info))
+            .and_then(|_| sorted_allowed.into_iter().try_for_each(|word| self.try_insert_allowed(word)));
+
+        if result.is_err() {
+            self.inner = inner;
+            self.allowlist = allowlist;
+            self.terms = terms;
+            self.allowed_words = allowed_words;
+        }
+
+        result
+    }
+
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    /**
+     * lets the char in the text match the equivalent char in the patterns, ex: '@' matches 'a'
+     * a char can have multiple equivalents, the char itself and all of its equivalents are matched at the same time
+     * the censoring still replaces the original chars of the text
+     */
+    pub fn add_equivalence(&mut self, from: char, to: char) {
+        let equivalents = self.equivalences.entry(from).or_default();
+
+        if !equivalents.contains(&to) {
+            equivalents.push(to);
+        }
+    }
+
+    pub fn remove_equivalences(&mut self, from: char) {
+        self.equivalences.remove(&from);
+    }
+
+    pub fn clear_equivalences(&mut self) {
+        self.equivalences.clear();
+    }
+
+    /**
+     * adds common leetspeak substitutions for lowercase latin letters
+     */
+    pub fn add_leetspeak_equivalences(&mut self) {
+        let substitutions = [
+            ('@', 'a'), ('4', 'a'), ('^', 'a'),
+            ('8', 'b'),
+            ('(', 'c'), ('<', 'c'),
+            ('3', 'e'),
+            ('6', 'g'), ('9', 'g'),
+            ('#', 'h'),
+            ('1', 'i'), ('!', 'i'), ('|', 'i'),
+            ('1', 'l'), ('!', 'l'), ('|', 'l'),
+            ('0', 'o'),
+            ('$', 's'), ('5', 's'),
+            ('7', 't'), ('+', 't'),
+            ('2', 'z'),
+        ];
+
+        for (from, to) in substitutions {
+            self.add_equivalence(from, to);
+        }
+    }
+
+    /**
+     * inserts the word with the default tags, the tags of a word that is already inserted are kept
+     */
+    pub fn insert(&mut self, word: &str) {
+        let _ = self.try_insert(word);
+    }
+
+    pub fn try_insert(&mut self, word: &str) -> Result<(), Error> {
+        self.inner.try_insert(&self.pattern(word))?;
+        self.register_term(word, None);
+        Ok(())
+    }
+
+    /**
+     * inserts the word with the tags, the tags of a word that is already inserted are replaced
+     */
+    pub fn insert_with(&mut self, word: &str, info: TermInfo) {
+        let _ = self.try_insert_with(word, info);
+    }
+
+    pub fn try_insert_with(&mut self, word: &str, info: TermInfo) -> Result<(), Error> {
+        check_weight(info.weight)?;
+        self.inner.try_insert(&self.pattern(word))?;
+        self.register_term(word, Some(info));
+        Ok(())
+    }
+
+    fn register_term(&mut self, word: &str, info: Option<TermInfo>) {
+        if let Some(node) = self.inner.find(&self.pattern(word)) {
+            let entry = self.terms.entry(node).or_insert_with(|| (word.to_string(), TermInfo::default()));
+
+            if let Some(info) = info {
+                entry.1 = info;
+            }
+        }
+    }
+
+    pub fn term_info(&self, word: &str) -> Option<TermInfo> {
+        self.inner.find(&self.pattern(word)).and_then(|node| self.terms.get(&node)).map(|(_, info)| *info)
+    }
+
+    pub fn remove(&mut self, word: &str) {
+        let _ = self.try_remove(word);
+    }
+
+    pub fn try_remove(&mut self, word: &str) -> Result<(), Error> {
+        let pattern = self.pattern(word);
+        let node = self.inner.find(&pattern);
+        self.inner.try_remove(&pattern)?;
+
+        if let Some(node) = node {
+            self.terms.remove(&node);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * returns a vector of tuples (index, length) in chars of the text, the text is normalized and folded like the words
+     */
+    pub fn search(&self, text: &str) -> Vec<(usize, usize)> {
+        self.try_search(text).unwrap_or_default()
+    }
+
+    pub fn try_search(&self, text: &str) -> Result<Vec<(usize, usize)>, Error> {
+        let normalized = NormalizedText::new(text, self.normalization);
+        let folded: Vec<char> = normalized.text.chars().map(|c| self.fold(c)).collect();
+
+        Ok(self.inner.try_search_sequence(&folded)?
+            .into_iter()
+            .map(|(index, length)| {
+                let (start, end) = normalized.original_chars(index, index + length);
+                (start, end - start)
+            })
+            .collect())
+    }
+
+    pub fn build(&mut self, words: Vec<&str>) {
+        let _ = self.try_build(words);
+    }
+
+    pub fn try_build(&mut self, words: Vec<&str>) -> Result<(), Error> {
+        let patterns: Vec<String> = words.iter().map(|word| self.pattern(word)).collect();
+        let result = self.inner.try_build(patterns.iter().map(|pattern| pattern.as_str()).collect());
+
+        // the words inserted before a failure are searchable so they are registered too
+        for word in words {
+            self.register_term(word, None);
+        }
+
+        result
+    }
+
+    /**
+     * time: O(n)
+     * returns filtered string
+     * matches are replaced according to the strategy, a &str replaces every censored char
+     * chars are unicode scalar values so multi byte chars are censored once
+     * the automaton is private so it can not be inconsistent, if it is the whole string is censored
+     */
+    pub fn filter<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S) -> String {
+        let strategy = strategy.into();
+        self.try_filter_with_strategy(string, &strategy).unwrap_or_else(|_| self.censor_all(string, &strategy, &IgnoreChars::default()))
+    }
+
+    pub fn try_filter<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S) -> Result<String, Error> {
+        self.try_filter_with_strategy(string, &strategy.into())
+    }
+
+    fn try_filter_with_strategy(&self, string: &str, strategy: &CensorStrategy) -> Result<String, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let ranges = self.censored_ranges(&characters, &IgnoreChars::default(), Severity::Low)?;
+
+        Ok(self.censor(&characters, &ranges, strategy, &IgnoreChars::default()))
+    }
+
+    /**
+     * time: O(n)
+     * returns filtered string where only the words with a severity at or above the threshold are censored
+     */
+    pub fn filter_with_threshold<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S, threshold: Severity) -> String {
+        let strategy = strategy.into();
+        self.try_filter_with_threshold_and_strategy(string, &strategy, threshold).unwrap_or_else(|_| self.censor_all(string, &strategy, &IgnoreChars::default()))
+    }
+
+    pub fn try_filter_with_threshold<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S, threshold: Severity) -> Result<String, Error> {
+        self.try_filter_with_threshold_and_strategy(string, &strategy.into(), threshold)
+    }
+
+    fn try_filter_with_threshold_and_strategy(&self, string: &str, strategy: &CensorStrategy, threshold: Severity) -> Result<String, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let ranges = self.censored_ranges(&characters, &IgnoreChars::default(), threshold)?;
+
+        Ok(self.censor(&characters, &ranges, strategy, &IgnoreChars::default()))
+    }
+
+    /**
+     * time: O(n)
+     * returns the string with every merged range that filter censors wrapped by the markup
+     */
+    pub fn highlight(&self, string: &str, markup: Markup) -> String {
+        self.try_highlight(string, markup).unwrap_or_else(|_| Self::mark(string, Self::whole_span(string), &markup))
+    }
+
+    pub fn try_highlight(&self, string: &str, markup: Markup) -> Result<String, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let ranges = self.censored_ranges(&characters, &IgnoreChars::default(), Severity::Low)?;
+
+        Ok(Self::mark(string, Self::to_spans(string, ranges), &markup))
+    }
+
+    /**
+     * time: O(n)
+     * returns the string with every merged range that filter_and_ignore_chars censors wrapped by the markup
+     * the ignored chars inside a match are wrapped with the match
+     */
+    pub fn highlight_and_ignore_chars<'b, I: Into<IgnoreChars<'b>>>(&self, string: &str, markup: Markup, ignore_chars: I) -> String {
+        self.try_highlight_and_ignore_chars(string, markup, ignore_chars).unwrap_or_else(|_| Self::mark(string, Self::whole_span(string), &markup))
+    }
+
+    pub fn try_highlight_and_ignore_chars<'b, I: Into<IgnoreChars<'b>>>(&self, string: &str, markup: Markup, ignore_chars: I) -> Result<String, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let ranges = self.censored_ranges(&characters, &ignore_chars.into(), Severity::Low)?;
+
+        Ok(Self::mark(string, Self::to_spans(string, ranges), &markup))
+    }
+
+    fn mark(string: &str, spans: Vec<CensorSpan>, markup: &Markup) -> String {
+        let mut output = String::with_capacity(string.len());
+        let mut byte_index = 0;
+
+        for span in spans {
+            markup.text(&string[byte_index..span.byte_start], &mut output);
+            markup.open(&mut output);
+            markup.text(&string[span.byte_start..span.byte_end], &mut output);
+            markup.close(&mut output);
+            byte_index = span.byte_end;
+        }
+
+        markup.text(&string[byte_index..], &mut output);
+        output
+    }
+
+    /**
+     * time: O(n)
+     * returns the merged ranges that filter censors, sorted and not overlapping
+     * if the filter fails the whole string is one span
+     */
+    pub fn censor_spans(&self, string: &str) -> Vec<CensorSpan> {
+        self.try_censor_spans(string).unwrap_or_else(|_| Self::whole_span(string))
+    }
+
+    pub fn try_censor_spans(&self, string: &str) -> Result<Vec<CensorSpan>, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let ranges = self.censored_ranges(&characters, &IgnoreChars::default(), Severity::Low)?;
+
+        Ok(Self::to_spans(string, ranges))
+    }
+
+    /**
+     * time: O(n)
+     * returns the ranges that filter_and_ignore_chars censors, sorted and not overlapping
+     * the ignored chars inside a match are not censored so a match is split into a range for every run of censored chars
+     */
+    pub fn censor_spans_and_ignore_chars<'b, I: Into<IgnoreChars<'b>>>(&self, string: &str, ignore_chars: I) -> Vec<CensorSpan> {
+        self.try_censor_spans_and_ignore_chars(string, ignore_chars).unwrap_or_else(|_| Self::whole_span(string))
+    }
+
+    pub fn try_censor_spans_and_ignore_chars<'b, I: Into<IgnoreChars<'b>>>(&self, string: &str, ignore_chars: I) -> Result<Vec<CensorSpan>, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let ignore_chars = ignore_chars.into();
+        let ranges = self.censored_ranges(&characters, &ignore_chars, Severity::Low)?;
+        let mut runs = Vec::new();
+
+        for (start, end) in ranges {
+            let mut run_start = start;
+
+            for (j, &c) in characters.iter().enumerate().take(end).skip(start) {
+                if ignore_chars.is_ignored(c) {
+                    if run_start < j {
+                        runs.push((run_start, j));
+                    }
+
+                    run_start = j + 1;
+                }
+            }
+
+            if run_start < end {
+                runs.push((run_start, end));
+            }
+        }
+
+        Ok(Self::to_spans(string, runs))
+    }
+
+    /**
+     * adds the byte indices to the char ranges
+     */
+    fn to_spans(string: &str, ranges: Vec<(usize, usize)>) -> Vec<CensorSpan> {
+        let mut byte_indices: Vec<usize> = string.char_indices().map(|(byte_index, _)| byte_index).collect();
+        byte_indices.push(string.len());
+
+        ranges
+            .into_iter()
+            .map(|(start, end)| CensorSpan { start, end, byte_start: byte_indices[start], byte_end: byte_indices[end] })
+            .collect()
+    }
+
+    /**
+     * returns a span of the whole string, no span for an empty string
+     */
+    fn whole_span(string: &str) -> Vec<CensorSpan> {
+        if string.is_empty() {
+            return Vec::new();
+        }
+
+        vec![CensorSpan { start: 0, end: string.chars().count(), byte_start: 0, byte_end: string.len() }]
+    }
+
+    /**
+     * time: O(n)
+     * returns every match that is not allowed with the tags of its word, and the highest severity of every category that was hit
+     * the hits are sorted by start
+     */
+    pub fn report(&self, string: &str) -> FilterReport {
+        self.try_report(string).unwrap_or_default()
+    }
+
+    pub fn try_report(&self, string: &str) -> Result<FilterReport, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let mut report = FilterReport {
+            invisible: characters.iter().enumerate().filter(|(_, &c)| is_default_ignorable(c)).map(|(i, _)| i).collect(),
+            ..FilterReport::default()
+        };
+
+        for found in self.find_matches(&characters, &IgnoreChars::default())? {
+            if let Some((term, info)) = self.terms.get(&found.node) {
+                report.add(TermHit { term: term.clone(), info: *info, start: found.start, end: found.end });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /**
+     * time: O(n)
+     * returns the score of the matches that are not allowed and the terms that were matched
+     */
+    pub fn score(&self, string: &str, options: ScoreOptions) -> Score {
+        self.try_score(string, options).unwrap_or_default()
+    }
+
+    pub fn try_score(&self, string: &str, options: ScoreOptions) -> Result<Score, Error> {
+        let report = self.try_report(string)?;
+        Score::new(&report.hits, string.chars().count(), options)
+    }
+
+    /**
+     * time: O(n)
+     * returns filtered string
+     * ignored chars are skipped when matching and are never censored
+     * ignore_chars can be a HashSet<char> or an IgnoreChars with classes, a predicate and a max gap
+     * the automaton is private so it can not be inconsistent, if it is the whole string is censored
+     */
+    pub fn filter_and_ignore_chars<'a, 'b, S: Into<CensorStrategy<'a>>, I: Into<IgnoreChars<'b>>>(&self, string: &str, strategy: S, ignore_chars: I) -> String {
+        let strategy = strategy.into();
+        let ignore_chars = ignore_chars.into();
+        self.try_filter_with_ignore_chars(string, &strategy, &ignore_chars).unwrap_or_else(|_| self.censor_all(string, &strategy, &ignore_chars))
+    }
+
+    pub fn try_filter_and_ignore_chars<'a, 'b, S: Into<CensorStrategy<'a>>, I: Into<IgnoreChars<'b>>>(&self, string: &str, strategy: S, ignore_chars: I) -> Result<String, Error> {
+        self.try_filter_with_ignore_chars(string, &strategy.into(), &ignore_chars.into())
+    }
+
+    fn try_filter_with_ignore_chars(&self, string: &str, strategy: &CensorStrategy, ignore_chars: &IgnoreChars) -> Result<String, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let ranges = self.censored_ranges(&characters, ignore_chars, Severity::Low)?;
+
+        Ok(self.censor(&characters, &ranges, strategy, ignore_chars))
+    }
+
+    /**
+     * time: O(n)
+     * filters the text of the reader into the writer chunk by chunk
+     * only the chars that can still be part of a match or of a merged range are kept in memory, the rest is written as soon as it is read
+     * the text is decoded as utf-8 and every invalid sequence is written as U+FFFD
+     */
+    pub fn filter_stream<'a, R: Read, W: Write, S: Into<CensorStrategy<'a>>>(&self, reader: R, writer: W, strategy: S) -> Result<(), Error> {
+        self.filter_stream_and_ignore_chars(reader, writer, strategy, IgnoreChars::default())
+    }
+
+    /**
+     * time: O(n)
+     * filter_stream with the ignored chars of filter_and_ignore_chars
+     * without a max gap a long run of ignored chars inside a match in progress is kept in memory
+     */
+    pub fn filter_stream_and_ignore_chars<'a, 'b, R: Read, W: Write, S: Into<CensorStrategy<'a>>, I: Into<IgnoreChars<'b>>>(&self, reader: R, mut writer: W, strategy: S, ignore_chars: I) -> Result<(), Error> {
+        let strategy = strategy.into();
+        let ignore_chars = ignore_chars.into();
+        let mut reader = BufReader::new(reader);
+        let mut decoder = LossyDecoder::default();
+        let mut state = StreamState::new(self, &ignore_chars);
+
+        loop {
+            let chunk = reader.fill_buf()?;
+            let length = chunk.len();
+            decoder.feed(chunk, length == 0, |c, _, _| state.push(c))?;
+
+            if length == 0 {
+                state.finish()?;
+            }
+
+            writer.write_all(state.release(&strategy, length == 0).as_bytes())?;
+
+            if length == 0 {
+                writer.flush()?;
+                return Ok(());
+            }
+
+            reader.consume(length);
+        }
+    }
+
+    /**
+     * time: O(n)
+     * returns the output of filter and why every censored span was censored
+     * if the automaton is inconsistent the whole string is censored as one span without terms
+     */
+    pub fn filter_explained<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S) -> FilterExplanation {
+        self.filter_explained_and_ignore_chars(string, strategy, IgnoreChars::default())
+    }
+
+    pub fn try_filter_explained<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S) -> Result<FilterExplanation, Error> {
+        self.try_filter_explained_and_ignore_chars(string, strategy, IgnoreChars::default())
+    }
+
+    /**
+     * time: O(n)
+     * returns the output of filter_and_ignore_chars and why every censored span was censored
+     */
+    pub fn filter_explained_and_ignore_chars<'a, 'b, S: Into<CensorStrategy<'a>>, I: Into<IgnoreChars<'b>>>(&self, string: &str, strategy: S, ignore_chars: I) -> FilterExplanation {
+        let strategy = strategy.into();
+        let ignore_chars = ignore_chars.into();
+
+        self.try_filter_explained_with(string, &strategy, &ignore_chars).unwrap_or_else(|_| FilterExplanation {
+            output: self.censor_all(string, &strategy, &ignore_chars),
+            spans: Self::whole_span(string)
+                .into_iter()
+                .map(|span| ExplainedSpan { span, original: string.to_string(), terms: Vec::new(), skipped: Vec::new(), rules: Vec::new() })
+                .collect(),
+        })
+    }
+
+    pub fn try_filter_explained_and_ignore_chars<'a, 'b, S: Into<CensorStrategy<'a>>, I: Into<IgnoreChars<'b>>>(&self, string: &str, strategy: S, ignore_chars: I) -> Result<FilterExplanation, Error> {
+        self.try_filter_explained_with(string, &strategy.into(), &ignore_chars.into())
+    }
+
+    fn try_filter_explained_with(&self, string: &str, strategy: &CensorStrategy, ignore_chars: &IgnoreChars) -> Result<FilterExplanation, Error> {
+        let characters: Vec<char> = string.chars().collect();
+        let matches = self.find_matches(&characters, ignore_chars)?;
+        let ranges = Self::merge_ranges(characters.len(), matches.iter().map(|found| (found.start, found.end)));
+        let output = self.censor(&characters, &ranges, strategy, ignore_chars);
+
+        // the matches and the ranges are both sorted by start so every match is visited once
+        let mut k = 0;
+        let mut spans = Vec::with_capacity(ranges.len());
+
+        for span in Self::to_spans(string, ranges) {
+            let mut terms: Vec<String> = Vec::new();
+
+            while k < matches.len() && matches[k].start < span.end {
+                if let Some((term, _)) = self.terms.get(&matches[k].node) {
+                    if !terms.contains(term) {
+                        terms.push(term.clone());
+                    }
+                }
+
+                k += 1;
+            }
+
+            // an invisible char that is part of a word was matched and not skipped, ex: the zero width joiners of an emoji sequence
+            let patterns: Vec<String> = terms.iter().map(|term| self.pattern(term)).collect();
+            let mut skipped = Vec::new();
+            let mut rules = Vec::new();
+
+            for (i, &c) in characters.iter().enumerate().take(span.end).skip(span.start) {
+                let is_invisible = self.skip_invisible && is_default_ignorable(c) && !patterns.iter().any(|pattern| pattern.contains(c));
+
+                if ignore_chars.is_ignored(c) || is_invisible {
+                    skipped.push((i, c));
+                }
+
+                for rule in self.normalization.rules_applied(c) {
+                    if !rules.contains(&rule) {
+                        rules.push(rule);
+                    }
+                }
+            }
+
+            spans.push(ExplainedSpan { original: string[span.byte_start..span.byte_end].to_string(), span, terms, skipped, rules });
+        }
+
+        Ok(FilterExplanation { output, spans })
+    }
+
+    /**
+     * time: O(n)
+     * returns the merged ranges (start, end) in char indices of every match with a severity at or above the threshold, end is exclusive
+     * the ranges are sorted and do not overlap
+     */
+    fn censored_ranges(&self, characters: &[char], ignore_chars: &IgnoreChars, threshold: Severity) -> Result<Vec<(usize, usize)>, Error> {
+        let matches = self.find_matches(characters, ignore_chars)?;
+
+        let ranges = matches
+            .iter()
+            .filter(|found| threshold == Severity::Low || self.terms.get(&found.node).is_some_and(|(_, info)| info.severity >= threshold))
+            .map(|found| (found.start, found.end));
+
+        Ok(Self::merge_ranges(characters.len(), ranges))
+    }
+
+    /**
+     * time: O(n)
+     * returns every match sorted by start
+     * matches that lie entirely inside an allowlist match are skipped
+     * a gap of ignored chars longer than the max gap ends every match in progress
+     */
+    fn find_matches(&self, characters: &[char], ignore_chars: &IgnoreChars) -> Result<Vec<FoundMatch>, Error> {
+        let mut matcher = Matcher::new(self, ignore_chars);
+
+        // empty string case is removed as it does not make sense in a filtering function
+
+        for (i, &c) in characters.iter().enumerate() {
+            matcher.feed(i, c)?;
+        }
+
+        matcher.finish()?;
+        Ok(matcher.take_matches(characters.len()))
+    }
+
+    /**
+     * merges the overlapping ranges
+     * returns the sorted ranges that do not overlap
+     */
+    fn merge_ranges<I: Iterator<Item = (usize, usize)>>(length: usize, matches: I) -> Vec<(usize, usize)> {
+        // ends[start] is the end of the longest match that starts at start
+        let mut ends: Vec<usize> = vec![0; length];
+
+        for (start, end) in matches {
+            ends[start] = max(ends[start], end);
+        }
+
+        let mut ranges = Vec::new();
+        let mut j = 0;
+
+        while j < length {
+            if ends[j] <= j {
+                j += 1;
+                continue;
+            }
+
+            let start = j;
+            let mut end = ends[j];
+
+            while j < end {
+                /*
+                intersection case:
+                0 * * *
+                  1 * * * * *
+
+                subset case:
+                0 * * * *
+                  1 * *
+
+                disjoint case:
+                0 * * * _ _
+                            6 * * *
+                */
+
+                end = max(end, ends[j]);
+                j += 1;
+            }
+
+            ranges.push((start, end));
+        }
+
+        ranges
+    }
+
+    /**
+     * time: O(t * e) where t is the number of threads and e the number of equivalents of the char
+     * feeds a char that is not ignored to the automaton and calls found with the range (start, end) and the pattern node of every match that ends at the char
+     * every equivalent of the char is followed so the text is matched in every node it can be in
+     * when only_children is true a thread that can not continue with the char is kept as it is, ex: an invisible char inside a match
+     */
+    fn step<F: FnMut(usize, usize, usize)>(&self, automaton: &AhoCorasick, state: &mut WalkState, i: usize, c: char, only_children: bool, mut found: F) -> Result<(), Error> {
+        let symbols = self.symbols(c);
+        let mut threads = std::mem::take(&mut state.threads);
+
+        if threads.is_empty() {
+            threads.push(Thread { node: automaton.root, last_symbol: None, history: VecDeque::new(), is_reported: false });
+        }
+
+        for mut thread in threads {
+            let children = &automaton.node(thread.node)?.children;
+            let has_child = symbols.iter().any(|symbol| children.contains_key(symbol));
+            let is_repeat = self.collapse_repeats && thread.last_symbol.is_some_and(|symbol| symbols.contains(&symbol));
+
+            if !has_child && (is_repeat || only_children) {
+                if thread.node != automaton.root {
+                    // the run stays in the current node, the history keeps the index of the first char of the run
+                    // the matches of the node are reported again so their ranges cover the run
+                    thread.is_reported = is_repeat;
+                    state.push(thread);
+                }
+
+                continue;
+            }
+
+            for &symbol in &symbols {
+                let next = self.goto(automaton, thread.node, symbol)?;
+
+                // no match is in progress so the chars before can not be part of a match
+                if next == automaton.root {
+                    continue;
+                }
+
+                let mut history = thread.history.clone();
+
+                if history.len() == state.capacity {
+                    history.pop_front();
+                }
+
+                history.push_back(i);
+                state.push(Thread { node: next, last_symbol: Some(symbol), history, is_reported: true });
+            }
+        }
+
+        for thread in state.threads.iter().filter(|thread| thread.is_reported) {
+            for &out_node in &automaton.node(thread.node)?.output_links {
+                let length = automaton.node(out_node)?.length;
+
+                if length == 0 {
+                    continue;
+                }
+
+                let history_index = thread.history.len().checked_sub(length).ok_or(Error::InvalidLength(out_node))?;
+                found(thread.history[history_index], i + 1, out_node);
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn insert(&mut self, word: &str) {
-        self.inner.insert(word)
+    /**
+     * returns the pattern chars the char can match, the folded char and its folded equivalents
+     */
+    fn symbols(&self, c: char) -> Vec<char> {
+        let mut symbols: Vec<char> = Vec::with_capacity(1);
+
+        for symbol in self.candidates(c).map(|candidate| self.fold(candidate)) {
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+
+        symbols
     }
 
-    pub fn remove(&mut self, word: &str) {
-        self.inner.remove(word)
+    /**
+     * returns the char followed by its equivalents
+     */
+    fn candidates(&self, c: char) -> impl Iterator<Item = char> + '_ {
+        let equivalents = self.equivalences.get(&c).map(|equivalents| equivalents.as_slice()).unwrap_or_default();
+        std::iter::once(c).chain(equivalents.iter().copied())
     }
 
-    pub fn search(&self, text: &str) -> Vec<(usize, usize)> {
-        self.inner.search(text)
+    /**
+     * returns the char in the case the patterns are stored in
+     * case insensitive chars are folded to the lowercase of their uppercase when both are single chars so chars with multiple lowercase forms match, ex: 'ς' and 'σ'
+     */
+    fn fold(&self, c: char) -> char {
+        match self.case_matching {
+            CaseMatching::Sensitive => c,
+            CaseMatching::AsciiInsensitive => c.to_ascii_lowercase(),
+            CaseMatching::Insensitive => {
+                let uppercase = single_char(c.to_uppercase()).unwrap_or(c);
+                single_char(uppercase.to_lowercase()).unwrap_or(uppercase)
+            }
+        }
     }
 
-    pub fn build(&mut self, words: Vec<&str>) {
-        self.inner.build(words)
+    /**
+     * returns the pattern that is inserted into the automaton for the word, the normalized word folded to one case
+     */
+    fn pattern(&self, word: &str) -> String {
+        self.normalization.normalize(word).chars().map(|c| self.fold(c)).collect()
     }
 
     /**
-     * time: O(n)
-     * returns filtered string
+     * follows the suffix links until the node has a child for the pattern char
+     * returns the child, or the root when no suffix has a child
      */
-    pub fn filter(&self, string: &str, censored_string: &str) -> String {
-        let mut node = self.inner.root;
-        let mut indices: HashMap<usize, usize> = HashMap::new();
+    fn goto(&self, automaton: &AhoCorasick, mut node: usize, symbol: char) -> Result<usize, Error> {
+        loop {
+            if let Some(&next) = automaton.node(node)?.children.get(&symbol) {
+                return Ok(next);
+            }
+            else if node == automaton.root {
+                return Ok(node);
+            }
 
-        // empty string case is removed as it does not make sense in a filtering function
+            node = automaton.suffix_link(node)?;
+        }
+    }
 
-        let characters: Vec<_> = string.chars().collect();
+    /**
+     * replaces the chars inside the ranges according to the strategy and keeps the chars outside
+     * the default ignorable chars are removed when strip_invisible is enabled
+     */
+    fn censor(&self, characters: &[char], ranges: &[(usize, usize)], strategy: &CensorStrategy, ignore_chars: &IgnoreChars) -> String {
+        let is_kept = |c: &char| !(self.strip_invisible && is_default_ignorable(*c));
+        let mut output = String::with_capacity(characters.len());
         let mut i = 0;
 
-        while i < characters.len() {
-            let c = characters[i];
-
-            if let Some(&next) = self.inner.nodes.get(&node).unwrap().children.get(&c) {
-                node = next;
-                i += 1;
+        for &(start, end) in ranges {
+            output.extend(characters[i..start].iter().filter(|c| is_kept(c)));
 
-                for &out_node in &self.inner.nodes.get(&node).unwrap().output_links {
-                    let len = self.inner.nodes.get(&out_node).unwrap().length;
-                    let start_index = i - len;
-
-                    if let Some(current) = indices.get(&start_index) {
-                        indices.insert(start_index, max(*current, len));
-                    }
-                    else {
-                        indices.insert(start_index, len);
-                    }
-                }
-            }
-            else if node == self.inner.root {
-                i += 1;
+            if self.strip_invisible {
+                let matched: Vec<char> = characters[start..end].iter().copied().filter(is_kept).collect();
+                strategy.censor(&matched, |c| ignore_chars.is_ignored(c), &mut output);
             }
             else {
-                node = self.inner.nodes.get(&node).unwrap().suffix_link.unwrap();
+                strategy.censor(&characters[start..end], |c| ignore_chars.is_ignored(c), &mut output);
             }
-        }
-
-        //---------
-
-        let mut output: String = String::new();
-        let mut j = 0;
-        
-        while j < string.len() {
-            if indices.contains_key(&j) {
-                // the found longer bound is always in the array bounds because of the DFA
-                // loop is unrolled by 1 iteration to have all loop logic in the required iterations
-                // all accept indices will have return lengths greater than 0
-                output += censored_string;
-
-                let mut end_index = j + indices.get(&j).unwrap();
-                j += 1;
 
-                while j < end_index {
-                    /*
-                    intersection case:
-                    0 * * *
-                      1 * * * * *
-
-                    subset case:
-                    0 * * * *
-                      1 * *
+            i = end;
+        }
 
-                    disjoint case:
-                    0 * * * _ _
-                                6 * * *
-                    */
+        output.extend(characters[i..].iter().filter(|c| is_kept(c)));
+        output
+    }
 
-                    if let Some(index_value) = indices.get(&j) {
-                        let second_end_index = j + index_value;
-                        end_index = max(second_end_index, end_index);
-                    }
+    /**
+     * censors the whole string, the filter functions without try_ return it when the filter fails so the text is never passed through uncensored
+     */
+    pub(crate) fn censor_all(&self, string: &str, strategy: &CensorStrategy, ignore_chars: &IgnoreChars) -> String {
+        let characters: Vec<char> = string.chars().collect();
 
-                    output += censored_string;
-                    j += 1;
-                }
-            }
-            else {
-                output += &characters[j].to_string();
-                j += 1;
-            }
+        if characters.is_empty() {
+            return String::new();
         }
 
-        output
+        self.censor(&characters, &[(0, characters.len())], strategy, ignore_chars)
     }
 
     /**
-     * time: O(n)
-     * returns filtered string
+     * filters text that was decoded from raw text, ex: a json string with escapes
+     * sources[k] is the byte range in raw of the decoded char k, the ranges are sorted and do not overlap
+     * the raw text of the chars that are not censored is kept, the output of the strategy is escaped into the raw text
+     * returns None if nothing is censored
      */
-    pub fn filter_and_ignore_chars(&self, string: &str, censored_string: &str, ignore_chars: HashSet<char>) -> String {
-        let mut node = self.inner.root;
-        let mut indices: HashMap<usize, usize> = HashMap::new();
+    pub(crate) fn filter_decoded<E: Fn(&str, &mut String)>(&self, raw: &str, characters: &[char], sources: &[(usize, usize)], strategy: &CensorStrategy, escape: E) -> Result<Option<String>, Error> {
+        let ranges = self.censored_ranges(characters, &IgnoreChars::default(), Severity::Low)?;
 
-        // empty string case is removed as it does not make sense in a filtering function
+        if ranges.is_empty() && !(self.strip_invisible && characters.iter().any(|&c| is_default_ignorable(c))) {
+            return Ok(None);
+        }
 
-        let characters: Vec<_> = string.chars().collect();
+        let mut output = String::with_capacity(raw.len());
+        // the end in raw of the last char that was written
+        let mut copied = 0;
         let mut i = 0;
 
-        while i < characters.len() {
-            let c = characters[i];
+        let keep = |output: &mut String, copied: &mut usize, start: usize, end: usize| {
+            for k in start..end {
+                let (source_start, source_end) = sources[k];
+                output.push_str(&raw[*copied..source_start]);
 
-            if ignore_chars.contains(&c) {
-                i += 1;
-            }
-            else if let Some(&next) = self.inner.nodes.get(&node).unwrap().children.get(&c) {
-                node = next;
-
-                for &out_node in &self.inner.nodes.get(&node).unwrap().output_links {
-                    let len = self.inner.nodes.get(&out_node).unwrap().length;
-
-                    if len != 0 {
-                        if let Some(current) = indices.get(&i) {
-                            indices.insert(i, max(*current, len));
-                        }
-                        else {
-                            indices.insert(i, len);
-                        }
-                    }
+                if !(self.strip_invisible && is_default_ignorable(characters[k])) {
+                    output.push_str(&raw[source_start..source_end]);
                 }
 
-                i += 1;
-            }
-            else if node == self.inner.root {
-                i += 1;
-            }
-            else {
-                node = self.inner.nodes.get(&node).unwrap().suffix_link.unwrap();
+                *copied = source_end;
             }
+        };
+
+        for &(start, end) in &ranges {
+            keep(&mut output, &mut copied, i, start);
+            output.push_str(&raw[copied..sources[start].0]);
+            escape(&self.censor(&characters[start..end], &[(0, end - start)], strategy, &IgnoreChars::default()), &mut output);
+            copied = sources[end - 1].1;
+            i = end;
         }
 
-        //---------
-
-        let mut output: String = String::new();
-        let mut j = string.len();
-
-        while j > 0 {
-            j -= 1;
+        keep(&mut output, &mut copied, i, characters.len());
+        output.push_str(&raw[copied..]);
 
-            if ignore_chars.contains(&characters[j]) || !indices.contains_key(&j) {
-                output += &characters[j].to_string();
-            }
-            else {
-                // the found bound is always in the array bounds because of the DFA
-                // loop is unrolled by 1 iteration to have all loop logic in the required iterations
-                // all accept indices will have return lengths greater than 0
-
-                output += censored_string;
-
-                let mut length = *indices.get(&j).unwrap();
-                let mut k = 1;
-
-                while k < length {
-                    j -= 1;
-
-                    /*
-                    intersection case:
-                    0 * * *
-                      1 * * * * *
+        Ok(Some(output))
+    }
+}
 
-                    subset case:
-                    0 * * * *
-                      1 * *
+/**
+ * returns the char if the iterator has exactly one char
+ */
+fn single_char<I: Iterator<Item = char>>(mut chars: I) -> Option<char> {
+    let first = chars.next();
 
-                    disjoint case:
-                    0 * * * _ _
-                                6 * * *
-                    */
+    if chars.next().is_none() {
+        first
+    }
+    else {
+        None
+    }
+}
 
-                    if ignore_chars.contains(&characters[j]) {
-                        output += &characters[j].to_string();
-                    }
-                    else {
-                        if let Some(other_length) = indices.get(&j) {
-                            if length - k < *other_length {
-                                length = other_length + k; // no minus 1 because k is 1 behind j here
-                            }
-                        }
-
-                        output += censored_string;
-                        k += 1;
-                    }
-                }
-            }
-        }
+#[cfg(feature = "rayon")]
+impl AhoCorasickFilter {
+    /**
+     * searches every document in parallel, every document is searched like search
+     * returns a vector of tuples (document index, matches) in the order of the documents
+     */
+    pub fn search_many(&self, documents: &[&str]) -> Vec<(usize, Vec<(usize, usize)>)> {
+        documents
+            .par_iter()
+            .enumerate()
+            .map(|(index, document)| (index, self.search(document)))
+            .collect()
+    }
 
-        output.chars().rev().collect::<String>()
+    /**
+     * filters every document in parallel
+     * returns a vector of tuples (document index, filtered string) in the order of the documents
+     */
+    pub fn filter_many<'a, S: Into<CensorStrategy<'a>>>(&self, documents: &[&str], strategy: S) -> Vec<(usize, String)> {
+        let strategy = strategy.into();
+
+        documents
+            .par_iter()
+            .enumerate()
+            .map(|(index, document)| (index, self.try_filter_with_strategy(document, &strategy).unwrap_or_else(|_| self.censor_all(document, &strategy, &IgnoreChars::default()))))
+            .collect()
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
+    use crate::data_structures::filter_term::Category;
+    use crate::data_structures::filter_term::ScoreAggregation;
+    use crate::data_structures::filter_term::TermScore;
+    use crate::data_structures::normalization::Normalization;
+    use crate::data_structures::normalization::NormalizationRule;
+    use crate::data_structures::ignore_chars::IgnoreClass;
+    use std::io::Read;
 
     fn vector_pair_to_string(pairs: Vec<(usize, usize)>) -> String {
         if pairs.is_empty() {
@@ -331,4 +1485,703 @@ mod tests {
         aho_corasick_filter.insert("135");
         assert_eq!(aho_corasick_filter.filter_and_ignore_chars("123456", "*", HashSet::from(['2', '4', '6'])), "*2*4*6");
     }
+
+    #[test]
+    fn aho_corasick_unicode_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "café", "cafe\u{301}", "日本", "💩", "👨\u{200d}👩\u{200d}👧", "straße", "ёж"]);
+
+        // emoji
+        assert_eq!(aho_corasick_filter.filter("🍎apple🍎", "*"), "🍎*****🍎");
+        assert_eq!(aho_corasick_filter.filter("a💩b💩💩", "*"), "a*b**");
+        assert_eq!(aho_corasick_filter.filter("👨\u{200d}👩\u{200d}👧 family", "*"), "***** family");
+        assert_eq!(aho_corasick_filter.filter("👨\u{200d}👩 couple", "*"), "👨\u{200d}👩 couple");
+
+        // precomposed and combining marks are different patterns, every scalar value is censored once
+        assert_eq!(aho_corasick_filter.filter("un café noir", "*"), "un **** noir");
+        assert_eq!(aho_corasick_filter.filter("un cafe\u{301} noir", "*"), "un ***** noir");
+        assert_eq!(aho_corasick_filter.filter("cafe\u{301}\u{301}", "*"), "*****\u{301}");
+        assert_eq!(aho_corasick_filter.filter("cafe", "*"), "cafe");
+
+        // mixed scripts
+        assert_eq!(aho_corasick_filter.filter("これは日本です", "*"), "これは**です");
+        assert_eq!(aho_corasick_filter.filter("日本apple日本", "#"), "#########");
+        assert_eq!(aho_corasick_filter.filter("die straße, ёжик и apple", "*"), "die ******, **ик и *****");
+        assert_eq!(aho_corasick_filter.filter("日本", "[x]"), "[x][x]");
+
+        // ignored chars can be multi byte
+        let ignore_chars = HashSet::from(['・', '\u{200b}', ' ']);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("日・本", "*", ignore_chars.clone()), "*・*");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a\u{200b}p p・le🍎", "*", ignore_chars.clone()), "*\u{200b}* *・**🍎");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("💩 💩", "[x]", ignore_chars.clone()), "[x] [x]");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("ё ж", "*", ignore_chars), "* *");
+
+        // the empty pattern does not censor anything
+        aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["", "apple", "bat"]);
+        assert_eq!(aho_corasick_filter.filter("bataapple", "*"), "***a*****");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("bataapple", "*", HashSet::new()), "***a*****");
+    }
+
+    #[test]
+    fn aho_corasick_strategy_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "app", "bat", "at", "日本"]);
+
+        assert_eq!(aho_corasick_filter.filter("an apple", CensorStrategy::Repeat("*")), "an *****");
+        assert_eq!(aho_corasick_filter.filter("an apple", CensorStrategy::KeepEnds("*")), "an a***e");
+        assert_eq!(aho_corasick_filter.filter("an apple", CensorStrategy::Grawlix("@#$%!")), "an @#$%!");
+        assert_eq!(aho_corasick_filter.filter("apples and bats", CensorStrategy::Grawlix("@#")), "@#@#@s and @#@s");
+        // an empty grawlix does not remove the match
+        assert_eq!(aho_corasick_filter.filter("an apple", CensorStrategy::Grawlix("")), "an *****");
+        assert_eq!(aho_corasick_filter.filter("an apple", CensorStrategy::Token("[removed]")), "an [removed]");
+        assert_eq!(aho_corasick_filter.filter("an apple", CensorStrategy::FixedWidth("*", 3)), "an ***");
+        assert_eq!(aho_corasick_filter.filter("app or apple", CensorStrategy::FixedWidth("*", 3)), "*** or ***");
+        assert_eq!(aho_corasick_filter.filter("an apple", CensorStrategy::custom(|matched| matched.to_uppercase())), "an APPLE");
+
+        // overlapping matches are merged and replaced as one match, adjacent matches are not merged
+        assert_eq!(aho_corasick_filter.filter("batapple", CensorStrategy::Token("[removed]")), "[removed][removed]");
+        assert_eq!(aho_corasick_filter.filter("bat", CensorStrategy::Token("[removed]")), "[removed]");
+        assert_eq!(aho_corasick_filter.filter("bat", CensorStrategy::KeepEnds("*")), "b*t");
+        assert_eq!(aho_corasick_filter.filter("at", CensorStrategy::KeepEnds("*")), "**");
+        assert_eq!(aho_corasick_filter.filter("日本", CensorStrategy::KeepEnds("*")), "**");
+        assert_eq!(aho_corasick_filter.filter("日本", CensorStrategy::Grawlix("@#")), "@#");
+        assert_eq!(aho_corasick_filter.filter("x日本x", CensorStrategy::custom(|matched| format!("<{}>", matched.chars().count()))), "x<2>x");
+
+        // ignored chars are kept by the per char strategies and replaced by the whole match strategies
+        let ignore_chars = HashSet::from(['.']);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p.p.l.e!", "*", ignore_chars.clone()), "*.*.*.*.*!");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p.p.l.e!", CensorStrategy::KeepEnds("*"), ignore_chars.clone()), "a.*.*.*.e!");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p.p.l.e!", CensorStrategy::Grawlix("@#$%!"), ignore_chars.clone()), "@.#.$.%.!!");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p.p.l.e!", CensorStrategy::Token("[removed]"), ignore_chars.clone()), "[removed]!");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p.p.l.e!", CensorStrategy::FixedWidth("#", 4), ignore_chars.clone()), "####!");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p.p.l.e!", CensorStrategy::custom(|matched| matched.replace('.', "")), ignore_chars), "apple!");
+    }
+
+    #[test]
+    fn aho_corasick_equivalence_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "list", "b1g"]);
+
+        assert_eq!(aho_corasick_filter.filter("@pp1e 4pple", "*"), "@pp1e 4pple");
+
+        aho_corasick_filter.add_leetspeak_equivalences();
+        assert_eq!(aho_corasick_filter.filter("@pp1e 4pple appl3 apple", "*"), "***** ***** ***** *****");
+        assert_eq!(aho_corasick_filter.filter("my |1$7", CensorStrategy::KeepEnds("*")), "my |**7");
+        assert_eq!(aho_corasick_filter.filter("@pp!e", CensorStrategy::custom(|matched| format!("<{}>", matched))), "<@pp!e>");
+
+        // the literal char is matched besides its equivalents
+        assert_eq!(aho_corasick_filter.filter("b1g big", "*"), "*** big");
+        assert_eq!(aho_corasick_filter.filter("0pple", "*"), "0pple");
+
+        let ignore_chars = HashSet::from(['.']);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("@.p.p.1.3", "*", ignore_chars), "*.*.*.*.*");
+
+        aho_corasick_filter.remove_equivalences('@');
+        assert_eq!(aho_corasick_filter.filter("@pp1e 4pple", "*"), "@pp1e *****");
+
+        aho_corasick_filter.clear_equivalences();
+        aho_corasick_filter.add_equivalence('ä', 'a');
+        assert_eq!(aho_corasick_filter.filter("äpple 4pple", "*"), "***** 4pple");
+        // every equivalent is followed, not only the first one that continues a match
+        aho_corasick_filter.build(vec!["idiot", "lol"]);
+        aho_corasick_filter.add_leetspeak_equivalences();
+        assert_eq!(aho_corasick_filter.filter("1o1 id1ot", "*"), "*** *****");
+
+        aho_corasick_filter.build(vec!["a1b", "alc"]);
+        assert_eq!(aho_corasick_filter.filter("a1c a1b", "*"), "*** ***");
+        assert_eq!(aho_corasick_filter.censor_spans("a1c"), vec![CensorSpan { start: 0, end: 3, byte_start: 0, byte_end: 3 }]);
+    }
+
+    #[test]
+    fn aho_corasick_collapse_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "bat", "nope"]);
+
+        assert_eq!(aho_corasick_filter.filter("appppple", "*"), "appppple");
+
+        aho_corasick_filter.set_collapse_repeats(true);
+        assert_eq!(aho_corasick_filter.filter("apple", "*"), "*****");
+        assert_eq!(aho_corasick_filter.filter("appppple", "*"), "********");
+        assert_eq!(aho_corasick_filter.filter("aaappplleee!", "*"), "***********!");
+        assert_eq!(aho_corasick_filter.filter("an aaappplleee", CensorStrategy::Token("[removed]")), "an [removed]");
+        assert_eq!(aho_corasick_filter.filter("baaaatttt bats", "*"), "********* ***s");
+        assert_eq!(aho_corasick_filter.filter("noooooope", "*"), "*********");
+
+        // a doubled char in a pattern needs a run of at least 2
+        assert_eq!(aho_corasick_filter.filter("aple", "*"), "aple");
+
+        // the run of the first char is part of the match
+        assert_eq!(aho_corasick_filter.filter("abbbat", "*"), "a*****");
+
+        let ignore_chars = HashSet::from([' ']);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a a p p p l e e", "*", ignore_chars), "* * * * * * * *");
+
+        aho_corasick_filter.add_leetspeak_equivalences();
+        assert_eq!(aho_corasick_filter.filter("@4@pp11e", "*"), "********");
+        assert_eq!(aho_corasick_filter.filter("n0o0pe", "*"), "******");
+
+        aho_corasick_filter.set_collapse_repeats(false);
+        assert_eq!(aho_corasick_filter.filter("noooooope", "*"), "noooooope");
+    }
+
+    #[test]
+    fn aho_corasick_allowlist_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["ass", "ssh"]);
+
+        assert_eq!(aho_corasick_filter.filter("the class on the grass, ass", "*"), "the cl*** on the gr***, ***");
+
+        aho_corasick_filter.build_allowed(vec!["class", "grass"]);
+        assert_eq!(aho_corasick_filter.filter("the class on the grass, ass", "*"), "the class on the grass, ***");
+        assert_eq!(aho_corasick_filter.filter("classass", "*"), "class***");
+
+        // a match that is only partly inside an allowed word is censored
+        assert_eq!(aho_corasick_filter.filter("classh", "*"), "cla***");
+        assert_eq!(aho_corasick_filter.filter("classh", CensorStrategy::Token("[removed]")), "cla[removed]");
+
+        let ignore_chars = HashSet::from(['.']);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("c.l.a.s.s a.s.s", "*", ignore_chars), "c.l.a.s.s *.*.*");
+
+        aho_corasick_filter.set_collapse_repeats(true);
+        assert_eq!(aho_corasick_filter.filter("classsss asss", "*"), "classsss ****");
+
+        aho_corasick_filter.add_leetspeak_equivalences();
+        assert_eq!(aho_corasick_filter.filter("cl@$$ @$$", "*"), "cl@$$ ***");
+
+        aho_corasick_filter.remove_allowed("class");
+        assert_eq!(aho_corasick_filter.filter("class grass", "*"), "cl*** grass");
+
+        assert!(aho_corasick_filter.try_insert_allowed("glass").is_ok());
+        assert_eq!(aho_corasick_filter.filter("glass", "*"), "glass");
+    }
+
+    #[test]
+    fn aho_corasick_large_dictionary_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        let blocked: Vec<String> = (0..10_000).map(|i| format!("bad{i}x")).collect();
+        let allowed: Vec<String> = (0..10_000).map(|i| format!("good{i}x")).collect();
+        aho_corasick_filter.build(blocked.iter().map(String::as_str).collect());
+        aho_corasick_filter.build_allowed(allowed.iter().map(String::as_str).collect());
+
+        // many short texts against large dictionaries, the cost of a call must not grow with the number of nodes
+        for i in (0..10_000).step_by(10) {
+            let text = format!("a bad{i}x and a good{i}x");
+            let expected = format!("a {} and a good{i}x", "*".repeat(format!("bad{i}x").len()));
+            assert_eq!(aho_corasick_filter.filter(&text, "*"), expected);
+        }
+    }
+
+    #[test]
+    fn aho_corasick_category_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.insert_with("darn", TermInfo::new(Category::Profanity, Severity::Low));
+        aho_corasick_filter.insert_with("heck", TermInfo::new(Category::Profanity, Severity::Medium));
+        aho_corasick_filter.insert_with("buy now", TermInfo::new(Category::Spam, Severity::High));
+        aho_corasick_filter.insert_with("555-0100", TermInfo::new(Category::Pii, Severity::Critical));
+        aho_corasick_filter.insert("apple");
+
+        assert_eq!(aho_corasick_filter.term_info("heck"), Some(TermInfo::new(Category::Profanity, Severity::Medium)));
+        assert_eq!(aho_corasick_filter.term_info("apple"), Some(TermInfo::default()));
+        assert_eq!(aho_corasick_filter.term_info("pear"), None);
+
+        let text = "darn, heck, buy now at 555-0100 apple";
+        assert_eq!(aho_corasick_filter.filter(text, "*"), "****, ****, ******* at ******** *****");
+        assert_eq!(aho_corasick_filter.filter_with_threshold(text, "*", Severity::Low), "****, ****, ******* at ******** *****");
+        assert_eq!(aho_corasick_filter.filter_with_threshold(text, "*", Severity::Medium), "darn, ****, ******* at ******** apple");
+        assert_eq!(aho_corasick_filter.filter_with_threshold(text, "*", Severity::High), "darn, heck, ******* at ******** apple");
+        assert_eq!(aho_corasick_filter.filter_with_threshold(text, CensorStrategy::Token("[pii]"), Severity::Critical), "darn, heck, buy now at [pii] apple");
+
+        let report = aho_corasick_filter.report(text);
+        assert!(!report.is_clean());
+        assert_eq!(report.max_severity(), Some(Severity::Critical));
+        assert_eq!(report.categories.len(), 4);
+        assert_eq!(report.categories.get(&Category::Profanity), Some(&Severity::Medium));
+        assert_eq!(report.categories.get(&Category::General), Some(&Severity::Low));
+        assert!(report.has_category(Category::Spam));
+        assert!(!report.has_category(Category::Slur));
+        assert_eq!(
+            report.hits.iter().map(|hit| (hit.term.as_str(), hit.start, hit.end)).collect::<Vec<_>>(),
+            vec![("darn", 0, 4), ("heck", 6, 10), ("buy now", 12, 19), ("555-0100", 23, 31), ("apple", 32, 37)]
+        );
+
+        // tags are kept by insert and replaced by insert_with
+        aho_corasick_filter.insert("heck");
+        assert_eq!(aho_corasick_filter.term_info("heck"), Some(TermInfo::new(Category::Profanity, Severity::Medium)));
+        aho_corasick_filter.insert_with("heck", TermInfo::new(Category::Slur, Severity::High));
+        assert_eq!(aho_corasick_filter.report("heck").categories.get(&Category::Slur), Some(&Severity::High));
+
+        aho_corasick_filter.remove("heck");
+        assert_eq!(aho_corasick_filter.term_info("heck"), None);
+        assert!(aho_corasick_filter.report("heck").is_clean());
+        assert_eq!(aho_corasick_filter.report("").max_severity(), None);
+
+        // allowed matches are not reported, collapsed runs are reported once
+        aho_corasick_filter.insert_allowed("pineapple");
+        aho_corasick_filter.set_collapse_repeats(true);
+        let report = aho_corasick_filter.report("pineapple appppple");
+        assert_eq!(report.hits, vec![TermHit { term: "apple".to_string(), info: TermInfo::default(), start: 10, end: 18 }]);
+    }
+
+    #[test]
+    fn aho_corasick_span_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "app", "bat", "日本"]);
+
+        let span = |start, end, byte_start, byte_end| CensorSpan { start, end, byte_start, byte_end };
+
+        assert_eq!(aho_corasick_filter.censor_spans(""), vec![]);
+        assert_eq!(aho_corasick_filter.censor_spans("nothing"), vec![]);
+        assert_eq!(aho_corasick_filter.censor_spans("an apple"), vec![span(3, 8, 3, 8)]);
+        assert_eq!(aho_corasick_filter.censor_spans("batapple bat"), vec![span(0, 3, 0, 3), span(3, 8, 3, 8), span(9, 12, 9, 12)]);
+        assert_eq!(aho_corasick_filter.censor_spans("é日本é apple"), vec![span(1, 3, 2, 8), span(5, 10, 11, 16)]);
+
+        let text = "é日本é apple";
+        for found in aho_corasick_filter.censor_spans(text) {
+            assert_eq!(text[found.byte_start..found.byte_end].chars().count(), found.end - found.start);
+        }
+
+        let ignore_chars = HashSet::from(['.', '・']);
+        assert_eq!(aho_corasick_filter.censor_spans_and_ignore_chars("a.pp.le!", ignore_chars.clone()), vec![span(0, 1, 0, 1), span(2, 4, 2, 4), span(5, 7, 5, 7)]);
+        assert_eq!(aho_corasick_filter.censor_spans_and_ignore_chars("日・本", ignore_chars.clone()), vec![span(0, 1, 0, 3), span(2, 3, 6, 9)]);
+        assert_eq!(aho_corasick_filter.censor_spans_and_ignore_chars(".bat.", ignore_chars), vec![span(1, 4, 1, 4)]);
+    }
+
+    #[test]
+    fn aho_corasick_highlight_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "app", "bat", "a&b", "日本"]);
+
+        assert_eq!(aho_corasick_filter.highlight("an apple", Markup::Html), "an <mark>apple</mark>");
+        assert_eq!(aho_corasick_filter.highlight("<b>batapple</b> & \"x\" 'y'", Markup::Html), "&lt;b&gt;<mark>bat</mark><mark>apple</mark>&lt;/b&gt; &amp; &quot;x&quot; &#39;y&#39;");
+        assert_eq!(aho_corasick_filter.highlight("a&b", Markup::Html), "<mark>a&amp;b</mark>");
+        assert_eq!(aho_corasick_filter.highlight("<日本>", Markup::HtmlClass("blur \"x\"")), "&lt;<mark class=\"blur &quot;x&quot;\">日本</mark>&gt;");
+        assert_eq!(aho_corasick_filter.highlight("no match <here>", Markup::Html), "no match &lt;here&gt;");
+
+        assert_eq!(aho_corasick_filter.highlight("an apple", Markup::Ansi("1;31")), "an \x1b[1;31mapple\x1b[0m");
+        assert_eq!(aho_corasick_filter.highlight("<apple>", Markup::Custom("[[", "]]")), "<[[apple]]>");
+        assert_eq!(aho_corasick_filter.highlight("", Markup::Custom("[[", "]]")), "");
+
+        let ignore_chars = HashSet::from(['.']);
+        assert_eq!(aho_corasick_filter.highlight_and_ignore_chars("<a.p.p>", Markup::Html, ignore_chars.clone()), "&lt;<mark>a.p.p</mark>&gt;");
+        assert_eq!(aho_corasick_filter.highlight_and_ignore_chars(".b.a.t.", Markup::Custom("{", "}"), ignore_chars), ".{b.a.t}.");
+    }
+
+    #[test]
+    fn aho_corasick_ignore_class_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "cafe"]);
+
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a p\tp\nl e", "*", IgnoreClass::Whitespace), "* *\t*\n* *");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p-p…l!e", "*", IgnoreClass::Punctuation), "*.*-*…*!*");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a\u{200b}pp\u{ad}l\u{202e}e", "*", IgnoreClass::Format), "*\u{200b}**\u{ad}*\u{202e}*");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("cafe\u{301}", "*", IgnoreClass::CombiningMark), "****\u{301}");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("ca\u{308}fe", "*", IgnoreClass::CombiningMark), "**\u{308}**");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.p p", "*", IgnoreClass::Whitespace), "a.p p");
+
+        let ignore_chars = IgnoreChars::new().class(IgnoreClass::Whitespace).class(IgnoreClass::Punctuation).chars(['_']);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a. p_p l-e", "*", ignore_chars), "*. *_* *-*");
+
+        let ignore_chars = IgnoreChars::new().predicate(|c| c.is_ascii_digit());
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a1p22p333le", "*", ignore_chars), "*1*22*333**");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a1p22p333le", "*", HashSet::new()), "a1p22p333le");
+    }
+
+    #[test]
+    fn aho_corasick_max_gap_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "pp"]);
+        aho_corasick_filter.build_allowed(vec!["ppl"]);
+
+        let ignore_chars = || IgnoreChars::from(IgnoreClass::Whitespace).max_gap(1);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a p p l e", "*", ignore_chars()), "* * * * *");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a p  p l e", "*", ignore_chars()), "a p  p l e");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a p p  l e", "*", ignore_chars()), "a * *  l e");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("   apple   ", "*", ignore_chars()), "   *****   ");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a . . . p p l e", "*", IgnoreChars::new().chars([' ', '.']).max_gap(2)), "a . . . p p l e");
+
+        let ignore_chars = IgnoreChars::from(IgnoreClass::Whitespace).max_gap(0);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a p p l e apple", "*", ignore_chars), "a p p l e *****");
+        assert_eq!(aho_corasick_filter.censor_spans_and_ignore_chars("p p pp", IgnoreChars::from(IgnoreClass::Whitespace).max_gap(0)), vec![CensorSpan { start: 4, end: 6, byte_start: 4, byte_end: 6 }]);
+    }
+
+    #[test]
+    fn aho_corasick_invisible_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "日本"]);
+
+        assert_eq!(aho_corasick_filter.filter("a\u{200b}pp\u{200d}le", "*"), "*******");
+        assert_eq!(aho_corasick_filter.filter("ap\u{ad}ple \u{202e}日\u{2060}本", "*"), "****** \u{202e}***");
+        assert_eq!(aho_corasick_filter.filter("\u{200b}apple\u{200b}", "*"), "\u{200b}*****\u{200b}");
+        assert_eq!(aho_corasick_filter.filter("a\u{200b}pple", CensorStrategy::Token("[x]")), "[x]");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a\u{200b} \u{200b} pple", "*", IgnoreChars::from(IgnoreClass::Whitespace).max_gap(2)), "** * ****");
+        assert_eq!(aho_corasick_filter.censor_spans("ap\u{ad}ple"), vec![CensorSpan { start: 0, end: 6, byte_start: 0, byte_end: 7 }]);
+
+        // invisible chars of a pattern are matched
+        aho_corasick_filter.insert("👨\u{200d}👩");
+        assert_eq!(aho_corasick_filter.filter("👨\u{200d}👩 👨👩 👨\u{200d}\u{200d}👩", "*"), "*** 👨👩 ****");
+
+        aho_corasick_filter.set_strip_invisible(true);
+        assert_eq!(aho_corasick_filter.filter("a\u{200b}pp\u{200d}le \u{feff}ok", "*"), "***** ok");
+        assert_eq!(aho_corasick_filter.filter("👨\u{200d}👩", "*"), "**");
+        assert_eq!(aho_corasick_filter.filter("a\u{200b}pple", CensorStrategy::custom(|matched| matched.to_uppercase())), "APPLE");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("a.\u{200b}p.p.l.e", "*", HashSet::from(['.'])), "*.*.*.*.*");
+
+        aho_corasick_filter.set_skip_invisible(false);
+        assert_eq!(aho_corasick_filter.filter("a\u{200b}pple apple", "*"), "apple *****");
+
+        let report = aho_corasick_filter.report("a\u{200b}pple \u{202e}");
+        assert!(report.is_clean());
+        assert!(report.has_invisible());
+        assert_eq!(report.invisible, vec![1, 7]);
+
+        aho_corasick_filter.set_skip_invisible(true);
+        let report = aho_corasick_filter.report("a\u{200b}pple");
+        assert_eq!(report.hits.len(), 1);
+        assert_eq!((report.hits[0].start, report.hits[0].end), (0, 6));
+        assert!(!aho_corasick_filter.report("apple").has_invisible());
+    }
+
+    #[test]
+    fn aho_corasick_case_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "Bat", "ёж", "σοφία", "straße"]);
+
+        assert_eq!(aho_corasick_filter.filter("Apple bat", "*"), "Apple bat");
+
+        aho_corasick_filter.set_case_matching(CaseMatching::AsciiInsensitive);
+        assert_eq!(aho_corasick_filter.filter("An APPLE, a BaT!", "*"), "An *****, a ***!");
+        assert_eq!(aho_corasick_filter.filter("ЁЖ ёж", "*"), "ЁЖ **");
+
+        aho_corasick_filter.set_case_matching(CaseMatching::Insensitive);
+        assert_eq!(aho_corasick_filter.filter("An APPLE, a BaT!", "*"), "An *****, a ***!");
+        assert_eq!(aho_corasick_filter.filter("ЁЖ Ёж", "*"), "** **");
+        assert_eq!(aho_corasick_filter.filter("ΣΟΦΊΑ ςοφία", "*"), "***** *****");
+        assert_eq!(aho_corasick_filter.filter("STRAßE STRASSE", "*"), "****** STRASSE");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("A.p.P.l.E", CensorStrategy::KeepEnds("*"), HashSet::from(['.'])), "A.*.*.*.E");
+        assert_eq!(aho_corasick_filter.report("APPLE").hits[0].term, "apple");
+
+        aho_corasick_filter.add_leetspeak_equivalences();
+        aho_corasick_filter.set_collapse_repeats(true);
+        aho_corasick_filter.build_allowed(vec!["Pineapple"]);
+        assert_eq!(aho_corasick_filter.filter("@PpPLE b4t", "*"), "****** ***");
+        assert_eq!(aho_corasick_filter.filter("PINEAPPLE pineApple", "*"), "PINEAPPLE pineApple");
+
+        aho_corasick_filter.set_case_matching(CaseMatching::Sensitive);
+        assert_eq!(aho_corasick_filter.filter("APPLE apple", "*"), "APPLE *****");
+
+        // words that share a prefix in different cases are one path of the automaton
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.set_case_matching(CaseMatching::Insensitive);
+        aho_corasick_filter.build(vec!["Apple", "apricot"]);
+        assert_eq!(aho_corasick_filter.filter("APRICOT aPPLE", "*"), "******* *****");
+        assert_eq!(aho_corasick_filter.search("APRICOT"), vec![(0, 7)]);
+
+        aho_corasick_filter.build(vec!["ab", "Bc"]);
+        assert_eq!(aho_corasick_filter.filter("abc", "*"), "***");
+
+        // the words inserted before the case matching changed are folded again
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["ab", "Bc"]);
+        aho_corasick_filter.set_case_matching(CaseMatching::AsciiInsensitive);
+        assert_eq!(aho_corasick_filter.filter("ABC", "*"), "***");
+        assert_eq!(aho_corasick_filter.report("ABC").hits[0].term, "ab");
+    }
+
+    /**
+     * returns at most 3 bytes per read so chars and matches are split between reads
+     */
+    struct SlowReader<'a> {
+        bytes: &'a [u8],
+    }
+
+    impl Read for SlowReader<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let length = buffer.len().min(self.bytes.len()).min(3);
+            buffer[..length].copy_from_slice(&self.bytes[..length]);
+            self.bytes = &self.bytes[length..];
+            Ok(length)
+        }
+    }
+
+    fn filter_stream_to_string(aho_corasick_filter: &AhoCorasickFilter, text: &str, strategy: CensorStrategy, ignore_chars: IgnoreChars) -> String {
+        let mut output = Vec::new();
+        aho_corasick_filter.filter_stream_and_ignore_chars(SlowReader { bytes: text.as_bytes() }, &mut output, strategy, ignore_chars).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn aho_corasick_stream_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "app", "bat", "apap", "日本", "ass", "aa"]);
+        aho_corasick_filter.build_allowed(vec!["class", "pineapple"]);
+
+        let texts = [
+            "",
+            "apple",
+            "batapapple bataapapple",
+            "a class of pineapples and an apple",
+            "日本 日・本 🍎apple🍎",
+            "aaaaaaaaaaaaaaaaaaaaaaaaa",
+            "a p p l e, a.p.p.l.e, a . . . p p l e",
+        ];
+
+        for text in texts {
+            assert_eq!(filter_stream_to_string(&aho_corasick_filter, text, "*".into(), IgnoreChars::default()), aho_corasick_filter.filter(text, "*"));
+            assert_eq!(filter_stream_to_string(&aho_corasick_filter, text, CensorStrategy::Token("[x]"), IgnoreChars::default()), aho_corasick_filter.filter(text, CensorStrategy::Token("[x]")));
+
+            let ignore_chars = || IgnoreChars::new().chars(['.', ' ', '・']).max_gap(2);
+            assert_eq!(filter_stream_to_string(&aho_corasick_filter, text, "*".into(), ignore_chars()), aho_corasick_filter.filter_and_ignore_chars(text, "*", ignore_chars()));
+        }
+
+        aho_corasick_filter.set_collapse_repeats(true);
+        aho_corasick_filter.set_case_matching(CaseMatching::Insensitive);
+        let text = "AAPPPPLLEE bat BBBAAATT";
+        assert_eq!(filter_stream_to_string(&aho_corasick_filter, text, "*".into(), IgnoreChars::default()), aho_corasick_filter.filter(text, "*"));
+
+        let long_text = "an apple a day keeps the doctor away. ".repeat(1000);
+        assert_eq!(filter_stream_to_string(&aho_corasick_filter, &long_text, "*".into(), IgnoreChars::default()), aho_corasick_filter.filter(&long_text, "*"));
+
+        let mut output = Vec::new();
+        aho_corasick_filter.filter_stream(&b"bat\xffapple\xe6\x97"[..], &mut output, "*").unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "***\u{fffd}*****\u{fffd}");
+    }
+
+    #[test]
+    fn aho_corasick_stream_memory_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple"]);
+
+        let text = "apple pie ".repeat(1000);
+        let ignore_chars = IgnoreChars::default();
+        let mut state = StreamState::new(&aho_corasick_filter, &ignore_chars);
+        let mut output = String::new();
+
+        for c in text.chars() {
+            state.push(c).unwrap();
+            output.push_str(&state.release(&"*".into(), false));
+
+            // only a prefix of "apple" is held back
+            assert!(state.pending.len() <= 5);
+        }
+
+        output.push_str(&state.release(&"*".into(), true));
+        assert_eq!(output, aho_corasick_filter.filter(&text, "*"));
+    }
+
+    #[test]
+    fn aho_corasick_normalization_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.insert_with("Apple", TermInfo::new(Category::Profanity, Severity::High));
+        aho_corasick_filter.build(vec!["file", "café"]);
+        aho_corasick_filter.insert_allowed("PINEAPPLE");
+
+        assert_eq!(aho_corasick_filter.filter("ａｐｐｌｅ äpple Apple", "*"), "ａｐｐｌｅ äpple *****");
+
+        aho_corasick_filter.set_normalization(Normalization::all());
+        assert_eq!(aho_corasick_filter.normalization(), Normalization::all());
+        assert_eq!(aho_corasick_filter.filter("ａｐｐｌｅ äpple ÄPPLE a\u{308}pple", "*"), "***** ***** ***** ******");
+        assert_eq!(aho_corasick_filter.filter("the ﬁle, the ＦＩＬＥ", "*"), "the ***, the ****");
+        assert_eq!(aho_corasick_filter.filter("un CAFE, un cafe\u{301}", CensorStrategy::Token("[x]")), "un [x], un [x]");
+        assert_eq!(aho_corasick_filter.filter("a pineäpple", "*"), "a pineäpple");
+        assert_eq!(aho_corasick_filter.highlight("<äpple>", Markup::Html), "&lt;<mark>äpple</mark>&gt;");
+        assert_eq!(aho_corasick_filter.censor_spans("ÄＰple!"), vec![CensorSpan { start: 0, end: 5, byte_start: 0, byte_end: 8 }]);
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("Ä.P.P.L.E", "*", HashSet::from(['.'])), "*.*.*.*.*");
+        assert_eq!(aho_corasick_filter.search("ａｐｐｌｅ ﬁle"), vec![(0, 5), (6, 3)]);
+
+        // a stripped mark at the end of a match is part of the match like in the censored span
+        assert_eq!(aho_corasick_filter.search("cafe\u{301}x"), vec![(0, 5)]);
+        assert_eq!(aho_corasick_filter.censor_spans("cafe\u{301}x"), vec![CensorSpan { start: 0, end: 5, byte_start: 0, byte_end: 6 }]);
+
+        assert_eq!(aho_corasick_filter.term_info("ÄPPLE"), Some(TermInfo::new(Category::Profanity, Severity::High)));
+        let report = aho_corasick_filter.report("ÄPPLE");
+        assert_eq!((report.hits[0].term.as_str(), report.hits[0].start, report.hits[0].end), ("Apple", 0, 5));
+
+        aho_corasick_filter.remove("APPLE");
+        assert_eq!(aho_corasick_filter.filter("äpple", "*"), "äpple");
+
+        aho_corasick_filter.set_normalization(Normalization::default());
+        assert_eq!(aho_corasick_filter.filter("file ﬁle café cafe\u{301}", "*"), "**** ﬁle **** cafe\u{301}");
+
+        // with only case folding the text is still decomposed and the marks are put in canonical order
+        aho_corasick_filter.set_normalization(Normalization { case_fold: true, ..Normalization::default() });
+        aho_corasick_filter.build(vec!["Äpple", "ạ\u{301}b"]);
+        assert_eq!(aho_corasick_filter.filter("äpple A\u{308}PPLE apple", "*"), "***** ****** apple");
+        assert_eq!(aho_corasick_filter.filter("xÁ\u{323}b a\u{301}\u{323}b", "*"), "x*** ****");
+        assert_eq!(aho_corasick_filter.filter("a\u{301}\u{323}", "*"), "a\u{301}\u{323}");
+
+        let mut output = Vec::new();
+        aho_corasick_filter.filter_stream("A\u{308}pple Á\u{323}b".as_bytes(), &mut output, "*").unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "****** ***");
+    }
+
+    #[test]
+    fn aho_corasick_score_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.insert_with("apple", TermInfo::new(Category::Profanity, Severity::High).with_weight(3.0));
+        aho_corasick_filter.insert_with("app", TermInfo::default().with_weight(0.5));
+        aho_corasick_filter.insert("bat");
+        aho_corasick_filter.insert_allowed("pineapple");
+
+        let score = aho_corasick_filter.score("apple bat apple bat bat", ScoreOptions::default());
+        assert_eq!(score.value, 3.0 * 2.0 + 0.5 * 2.0 + 3.0);
+        assert_eq!(score.terms, vec![
+            TermScore { term: "app".to_string(), weight: 0.5, count: 2, contribution: 1.0 },
+            TermScore { term: "apple".to_string(), weight: 3.0, count: 2, contribution: 6.0 },
+            TermScore { term: "bat".to_string(), weight: 1.0, count: 3, contribution: 3.0 },
+        ]);
+
+        let options = ScoreOptions { aggregation: ScoreAggregation::Max, ..ScoreOptions::default() };
+        let score = aho_corasick_filter.score("bat app apple", options);
+        assert_eq!(score.value, 3.0);
+        assert_eq!(score.terms.iter().map(|term| term.contribution).collect::<Vec<f64>>(), vec![0.0, 0.0, 3.0]);
+
+        let options = ScoreOptions { aggregation: ScoreAggregation::CappedPerTerm(1), ..ScoreOptions::default() };
+        assert_eq!(aho_corasick_filter.score("bat bat bat apple apple", options).value, 1.0 + 0.5 + 3.0);
+
+        let options = ScoreOptions { per_chars: Some(100), ..ScoreOptions::default() };
+        assert_eq!(aho_corasick_filter.score("bat", options).value, 100.0 / 3.0);
+        assert_eq!(aho_corasick_filter.score(&format!("bat{}", " ".repeat(97)), options).value, 1.0);
+
+        assert_eq!(aho_corasick_filter.score("a pineapple", ScoreOptions::default()), Score::default());
+        assert_eq!(aho_corasick_filter.score("", options), Score::default());
+
+        // a weight has to be finite and at least 0
+        for weight in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -1.0] {
+            assert!(matches!(TermInfo::default().try_with_weight(weight), Err(Error::InvalidWeight(_))));
+            assert!(matches!(aho_corasick_filter.try_insert_with("cat", TermInfo { weight, ..TermInfo::default() }), Err(Error::InvalidWeight(_))));
+        }
+
+        assert_eq!(TermInfo::default().try_with_weight(0.0).unwrap().weight, 0.0);
+        assert_eq!(TermInfo::default().with_weight(2.0).with_weight(-1.0).weight, 2.0);
+        assert_eq!(aho_corasick_filter.term_info("cat"), None);
+
+        let hits = [TermHit { term: "cat".to_string(), info: TermInfo { weight: f64::NAN, ..TermInfo::default() }, start: 0, end: 3 }];
+        assert!(matches!(Score::new(&hits, 3, ScoreOptions::default()), Err(Error::InvalidWeight(_))));
+    }
+
+    #[test]
+    fn aho_corasick_explained_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "app", "bat", "👨\u{200d}👩"]);
+        aho_corasick_filter.insert_allowed("pineapple");
+
+        let explanation = aho_corasick_filter.filter_explained("a batapple, a pineapple and an app", "*");
+        assert_eq!(explanation.output, "a ********, a pineapple and an ***");
+        assert_eq!(explanation.spans, vec![
+            ExplainedSpan {
+                span: CensorSpan { start: 2, end: 5, byte_start: 2, byte_end: 5 },
+                original: "bat".to_string(),
+                terms: vec!["bat".to_string()],
+                skipped: vec![],
+                rules: vec![],
+            },
+            ExplainedSpan {
+                span: CensorSpan { start: 5, end: 10, byte_start: 5, byte_end: 10 },
+                original: "apple".to_string(),
+                terms: vec!["app".to_string(), "apple".to_string()],
+                skipped: vec![],
+                rules: vec![],
+            },
+            ExplainedSpan {
+                span: CensorSpan { start: 31, end: 34, byte_start: 31, byte_end: 34 },
+                original: "app".to_string(),
+                terms: vec!["app".to_string()],
+                skipped: vec![],
+                rules: vec![],
+            },
+        ]);
+
+        let explanation = aho_corasick_filter.filter_explained_and_ignore_chars("b.a.t a\u{200b}pp 👨\u{200d}👩", CensorStrategy::Token("[x]"), HashSet::from(['.']));
+        assert_eq!(explanation.output, "[x] [x] [x]");
+        assert_eq!(explanation.spans.iter().map(|span| span.skipped.clone()).collect::<Vec<Vec<(usize, char)>>>(), vec![vec![(1, '.'), (3, '.')], vec![(7, '\u{200b}')], vec![]]);
+        assert_eq!(explanation.spans[2].original, "👨\u{200d}👩");
+
+        aho_corasick_filter.set_normalization(Normalization::all());
+        let explanation = aho_corasick_filter.filter_explained("ÄＰＰle bat", "#");
+        assert_eq!(explanation.output, "##### ###");
+        assert_eq!(explanation.spans[0].original, "ÄＰＰle");
+        assert_eq!(explanation.spans[0].rules, vec![NormalizationRule::CaseFold, NormalizationRule::StripDiacritics, NormalizationRule::Compatibility]);
+        assert_eq!(explanation.spans[1].rules, vec![]);
+
+        assert_eq!(aho_corasick_filter.filter_explained("", "*"), FilterExplanation::default());
+    }
+
+    #[test]
+    fn aho_corasick_fail_closed_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["bad"]);
+
+        // an inconsistent automaton censors everything instead of returning the text unfiltered
+        let node = aho_corasick_filter.inner.nodes[&aho_corasick_filter.inner.root].children[&'b'];
+        aho_corasick_filter.inner.nodes.get_mut(&node).unwrap().suffix_link = None;
+
+        assert!(aho_corasick_filter.try_filter("bx", "*").is_err());
+        assert_eq!(aho_corasick_filter.filter("bx", "*"), "**");
+        assert_eq!(aho_corasick_filter.filter("", "*"), "");
+        assert_eq!(aho_corasick_filter.filter_with_threshold("bx", "*", Severity::Low), "**");
+        assert_eq!(aho_corasick_filter.filter_and_ignore_chars("b x", "*", HashSet::from([' '])), "* *");
+        assert_eq!(aho_corasick_filter.censor_spans("bx"), vec![CensorSpan { start: 0, end: 2, byte_start: 0, byte_end: 2 }]);
+        assert_eq!(aho_corasick_filter.filter_explained("bx", "*").output, "**");
+        assert!(aho_corasick_filter.try_filter_json("[\"bx\"]", "*").is_err());
+        assert_eq!(aho_corasick_filter.filter_html("<b>bx</b>", "*"), "*********");
+        assert_eq!(aho_corasick_filter.filter_markdown("bx", "*"), "\\*\\*");
+    }
+
+    #[test]
+    fn aho_corasick_limits_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::with_limits(Limits { max_pattern_length: 3, ..Limits::default() });
+        assert!(matches!(aho_corasick_filter.try_insert("toolong"), Err(Error::PatternTooLong { .. })));
+
+        // the functions without try_ skip the word like the ones of AhoCorasick
+        aho_corasick_filter.insert("toolong");
+        aho_corasick_filter.insert("bad");
+        aho_corasick_filter.insert_allowed("toolong");
+        assert_eq!(aho_corasick_filter.filter("toolong bad", "*"), "toolong ***");
+
+        // a setting that makes a word too long leaves the filter unchanged
+        aho_corasick_filter.insert("ﬁﬁ");
+        assert!(matches!(aho_corasick_filter.try_set_normalization(Normalization::all()), Err(Error::PatternTooLong { .. })));
+        assert_eq!(aho_corasick_filter.normalization(), Normalization::default());
+        assert_eq!(aho_corasick_filter.filter("bad ﬁﬁ fifi", "*"), "*** ** fifi");
+
+        aho_corasick_filter.set_normalization(Normalization::all());
+        assert_eq!(aho_corasick_filter.normalization(), Normalization::default());
+        assert_eq!(aho_corasick_filter.term_info("ﬁﬁ"), Some(TermInfo::default()));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn aho_corasick_parallel_tests() {
+        let mut aho_corasick_filter = AhoCorasickFilter::new();
+        aho_corasick_filter.build(vec!["apple", "app", "bat"]);
+
+        let documents = vec!["apple", "", "bataapple", "nothing here"];
+
+        assert_eq!(
+            aho_corasick_filter.filter_many(&documents, "*"),
+            vec![
+                (0, "*****".to_string()),
+                (1, "".to_string()),
+                (2, "***a*****".to_string()),
+                (3, "nothing here".to_string())
+            ]
+        );
+
+        let results = aho_corasick_filter.search_many(&documents);
+        assert_eq!(results.len(), documents.len());
+        assert_eq!(results[3], (3, vec![]));
+
+        // the documents are searched with the settings of the filter
+        aho_corasick_filter.set_normalization(Normalization::all());
+        aho_corasick_filter.insert("café");
+
+        let documents = vec!["CAFE", "ＡＰＰＬＥ", "Bat café", "cafe\u{301}"];
+        let results = aho_corasick_filter.search_many(&documents);
+        assert_eq!(results[0], (0, vec![(0, 4)]));
+
+        for (index, document) in documents.iter().enumerate() {
+            assert_eq!(results[index], (index, aho_corasick_filter.search(document)));
+        }
+    }
 }
@@ -0,0 +1,527 @@
+use super::aho_corasick_filter::AhoCorasickFilter;
+use super::censor_strategy::CensorStrategy;
+use crate::error::Error;
+
+/**
+ * a string of the json with the decoded chars and the byte range of every char in the json
+ */
+struct JsonString {
+    characters: Vec<char>,
+    sources: Vec<(usize, usize)>,
+    // byte index after the closing quote
+    end: usize,
+}
+
+/**
+ * an open object or array
+ */
+struct Container {
+    is_object: bool,
+    // the key of the current value of an object or the index of the current value of an array
+    key: String,
+    index: usize,
+}
+
+/**
+ * a segment of a json path
+ */
+enum PathSegment {
+    /// * matches any key or index
+    Any,
+    /// a key of an object or an index of an array
+    Key(String),
+}
+
+/**
+ * splits the path at the dots, a backslash escapes a dot, a star or a backslash of a key, ex: r"a\.b" is the key "a.b"
+ */
+fn parse_path(path: &str) -> Result<Vec<PathSegment>, Error> {
+    if path.is_empty() {
+        return Err(Error::InvalidPath(path.to_string()));
+    }
+
+    let mut segments = Vec::new();
+    let mut key = String::new();
+    let mut is_escaped = false;
+    let mut characters = path.chars();
+
+    loop {
+        match characters.next() {
+            Some('\\') => match characters.next() {
+                Some(c @ ('.' | '*' | '\\')) => {
+                    key.push(c);
+                    is_escaped = true;
+                }
+                _ => return Err(Error::InvalidPath(path.to_string())),
+            },
+            Some(c) if c != '.' => key.push(c),
+            end => {
+                let key = std::mem::take(&mut key);
+                segments.push(if key == "*" && !is_escaped { PathSegment::Any } else { PathSegment::Key(key) });
+                is_escaped = false;
+
+                if end.is_none() {
+                    return Ok(segments);
+                }
+            }
+        }
+    }
+}
+
+struct JsonFilter<'f, 's, 'p> {
+    filter: &'f AhoCorasickFilter,
+    strategy: &'s CensorStrategy<'s>,
+    // None filters every string value
+    paths: Option<Vec<Vec<PathSegment>>>,
+    json: &'p str,
+    bytes: &'p [u8],
+    position: usize,
+    containers: Vec<Container>,
+    output: String,
+    // the end of the json that was copied to the output
+    copied: usize,
+}
+
+impl<'f, 's, 'p> JsonFilter<'f, 's, 'p> {
+    fn error(&self, message: String) -> Error {
+        let line = self.json.as_bytes()[..self.position.min(self.json.len())].iter().filter(|&&byte| byte == b'\n').count() + 1;
+        Error::Parse { line, message }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.bytes.len() && matches!(self.bytes[self.position], b' ' | b'\t' | b'\n' | b'\r') {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Result<u8, Error> {
+        self.bytes.get(self.position).copied().ok_or_else(|| self.error("unexpected end of json".to_string()))
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Error> {
+        self.skip_whitespace();
+
+        if self.peek()? != byte {
+            return Err(self.error(format!("expected '{}', found '{}'", byte as char, self.found())));
+        }
+
+        self.position += 1;
+        Ok(())
+    }
+
+    fn found(&self) -> char {
+        self.json[self.position..].chars().next().unwrap_or_default()
+    }
+
+    fn run(mut self) -> Result<String, Error> {
+        self.value()?;
+
+        loop {
+            self.skip_whitespace();
+
+            let Some(is_object) = self.containers.last().map(|container| container.is_object) else {
+                break;
+            };
+
+            let close = if is_object { b'}' } else { b']' };
+
+            match self.peek()? {
+                b',' => {
+                    self.position += 1;
+
+                    if let Some(container) = self.containers.last_mut() {
+                        container.index += 1;
+                    }
+
+                    if is_object {
+                        self.key()?;
+                    }
+
+                    self.value()?;
+                }
+                byte if byte == close => {
+                    self.position += 1;
+                    self.containers.pop();
+                }
+                _ => return Err(self.error(format!("expected ',' or '{}', found '{}'", close as char, self.found()))),
+            }
+        }
+
+        if self.position != self.bytes.len() {
+            return Err(self.error(format!("unexpected '{}' after the json value", self.found())));
+        }
+
+        self.output.push_str(&self.json[self.copied..]);
+        Ok(self.output)
+    }
+
+    /**
+     * reads the key of an object value and the colon after it
+     */
+    fn key(&mut self) -> Result<(), Error> {
+        self.skip_whitespace();
+
+        if self.peek()? != b'"' {
+            return Err(self.error(format!("expected a key, found '{}'", self.found())));
+        }
+
+        let string = self.string()?;
+        self.position = string.end;
+
+        if let Some(container) = self.containers.last_mut() {
+            container.key = string.characters.into_iter().collect();
+        }
+
+        self.expect(b':')
+    }
+
+    /**
+     * reads a value, an object or an array is opened and its first value is read
+     * the containers are kept on a stack instead of recursing so deeply nested json can not overflow the stack
+     */
+    fn value(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip_whitespace();
+
+            let is_object = match self.peek()? {
+                b'{' => true,
+                b'[' => false,
+                _ => return self.scalar(),
+            };
+
+            let close = if is_object { b'}' } else { b']' };
+            self.position += 1;
+            self.skip_whitespace();
+
+            if self.peek()? == close {
+                self.position += 1;
+                return Ok(());
+            }
+
+            self.containers.push(Container { is_object, key: String::new(), index: 0 });
+
+            if is_object {
+                self.key()?;
+            }
+        }
+    }
+
+    fn scalar(&mut self) -> Result<(), Error> {
+        match self.peek()? {
+            b'"' => {
+                let start = self.position;
+                let string = self.string()?;
+                self.position = string.end;
+
+                if self.is_selected() {
+                    let raw = &self.json[start + 1..string.end - 1];
+                    let sources: Vec<(usize, usize)> = string.sources.iter().map(|&(source_start, source_end)| (source_start - start - 1, source_end - start - 1)).collect();
+
+                    if let Some(filtered) = self.filter.filter_decoded(raw, &string.characters, &sources, self.strategy, escape_json)? {
+                        self.output.push_str(&self.json[self.copied..start + 1]);
+                        self.output.push_str(&filtered);
+                        self.copied = string.end - 1;
+                    }
+                }
+
+                Ok(())
+            }
+            b't' => self.literal("true"),
+            b'f' => self.literal("false"),
+            b'n' => self.literal("null"),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(self.error(format!("expected a value, found '{}'", self.found()))),
+        }
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<(), Error> {
+        if !self.json[self.position..].starts_with(literal) {
+            return Err(self.error(format!("expected {}", literal)));
+        }
+
+        self.position += literal.len();
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<(), Error> {
+        let digits = |json: &mut Self| {
+            let start = json.position;
+
+            while json.position < json.bytes.len() && json.bytes[json.position].is_ascii_digit() {
+                json.position += 1;
+            }
+
+            json.position - start
+        };
+
+        if self.bytes[self.position] == b'-' {
+            self.position += 1;
+        }
+
+        let start = self.position;
+        let integer_digits = digits(self);
+
+        if integer_digits == 0 || (integer_digits > 1 && self.bytes[start] == b'0') {
+            return Err(self.error("invalid number".to_string()));
+        }
+
+        if self.bytes.get(self.position) == Some(&b'.') {
+            self.position += 1;
+
+            if digits(self) == 0 {
+                return Err(self.error("invalid number".to_string()));
+            }
+        }
+
+        if matches!(self.bytes.get(self.position), Some(b'e' | b'E')) {
+            self.position += 1;
+
+            if matches!(self.bytes.get(self.position), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+
+            if digits(self) == 0 {
+                return Err(self.error("invalid number".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * decodes the string that starts at the position, the position is not moved
+     * a lone surrogate escape is decoded as U+FFFD and kept as it is unless it is censored
+     */
+    fn string(&mut self) -> Result<JsonString, Error> {
+        let mut characters = Vec::new();
+        let mut sources = Vec::new();
+        let start = self.position;
+        let mut i = start + 1;
+
+        loop {
+            let Some(c) = self.json[i..].chars().next() else {
+                self.position = i;
+                return Err(self.error("unterminated string".to_string()));
+            };
+
+            match c {
+                '"' => break,
+                '\\' => {
+                    let escape = self.bytes.get(i + 1).copied().unwrap_or_default();
+                    let decoded = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let high = self.hex(i)?;
+
+                            if (0xd800..0xdc00).contains(&high) && self.bytes.get(i + 6) == Some(&b'\\') && self.bytes.get(i + 7) == Some(&b'u') {
+                                let low = self.hex(i + 6)?;
+
+                                if (0xdc00..0xe000).contains(&low) {
+                                    let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                                    characters.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                                    sources.push((i, i + 12));
+                                    i += 12;
+                                    continue;
+                                }
+                            }
+
+                            characters.push(char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER));
+                            sources.push((i, i + 6));
+                            i += 6;
+                            continue;
+                        }
+                        _ => {
+                            self.position = i;
+                            return Err(self.error("invalid escape".to_string()));
+                        }
+                    };
+
+                    characters.push(decoded);
+                    sources.push((i, i + 2));
+                    i += 2;
+                }
+                c if c < ' ' => {
+                    self.position = i;
+                    return Err(self.error("unescaped control char in string".to_string()));
+                }
+                c => {
+                    characters.push(c);
+                    sources.push((i, i + c.len_utf8()));
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        Ok(JsonString { characters, sources, end: i + 1 })
+    }
+
+    /**
+     * returns the code of the \uXXXX escape at the index
+     */
+    fn hex(&mut self, index: usize) -> Result<u32, Error> {
+        match self.json.get(index + 2..index + 6).filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit())) {
+            Some(digits) => Ok(u32::from_str_radix(digits, 16).unwrap_or_default()),
+            None => {
+                self.position = index;
+                Err(self.error("invalid unicode escape".to_string()))
+            }
+        }
+    }
+
+    /**
+     * returns true if the current value is inside one of the paths
+     */
+    fn is_selected(&self) -> bool {
+        let Some(paths) = &self.paths else {
+            return true;
+        };
+
+        paths.iter().any(|path| {
+            path.len() <= self.containers.len() && path.iter().zip(&self.containers).all(|(segment, container)| match segment {
+                PathSegment::Any => true,
+                PathSegment::Key(key) if container.is_object => *key == container.key,
+                PathSegment::Key(key) => key.parse::<usize>().ok() == Some(container.index),
+            })
+        })
+    }
+}
+
+/**
+ * escapes the text for a json string, only quotes, backslashes and control chars are escaped
+ */
+fn escape_json(text: &str, output: &mut String) {
+    for c in text.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{8}' => output.push_str("\\b"),
+            '\u{c}' => output.push_str("\\f"),
+            c if c < ' ' => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+}
+
+impl AhoCorasickFilter {
+    /**
+     * time: O(n)
+     * filters every string value of the json, keys, numbers and literals are never filtered
+     * escapes are decoded before matching, ex: "b\u0061d" matches bad
+     * everything that is not censored is kept byte for byte including the whitespace and the escapes
+     * there is no version without try_ because no string can replace an invalid json and still be json
+     * the error of an invalid json is Error::Parse with the line number starting at 1
+     */
+    pub fn try_filter_json<'a, S: Into<CensorStrategy<'a>>>(&self, json: &str, strategy: S) -> Result<String, Error> {
+        self.filter_json_with(json, &strategy.into(), None)
+    }
+
+    /**
+     * time: O(n)
+     * filters the string values of the json that are inside one of the paths
+     * a path is keys and array indices separated by dots, * matches any key or index, ex: "comments.*.text"
+     * a backslash escapes a dot, a star or a backslash of a key, ex: r"a\.b" is the key "a.b" and r"\*" is the key "*"
+     * every string value inside a path is filtered, ex: "user" filters "user.name" and "user.bio"
+     * an empty path is Error::InvalidPath, use try_filter_json to filter every string value
+     */
+    pub fn try_filter_json_paths<'a, S: Into<CensorStrategy<'a>>>(&self, json: &str, strategy: S, paths: &[&str]) -> Result<String, Error> {
+        let paths = paths.iter().map(|path| parse_path(path)).collect::<Result<Vec<Vec<PathSegment>>, Error>>()?;
+        self.filter_json_with(json, &strategy.into(), Some(paths))
+    }
+
+    fn filter_json_with(&self, json: &str, strategy: &CensorStrategy, paths: Option<Vec<Vec<PathSegment>>>) -> Result<String, Error> {
+        JsonFilter {
+            filter: self,
+            strategy,
+            paths,
+            json,
+            bytes: json.as_bytes(),
+            position: 0,
+            containers: Vec::new(),
+            output: String::with_capacity(json.len()),
+            copied: 0,
+        }.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aho_corasick_json_tests() {
+        let mut filter = AhoCorasickFilter::new();
+        filter.build(vec!["bad", "apple", "日本"]);
+
+        let json = r#"{ "bad": "a bad apple", "list": [1, -2.5e3, true, null, "bad"], "nested": {"apple": {"text": "no"}} }"#;
+        assert_eq!(filter.try_filter_json(json, "*").unwrap(), r#"{ "bad": "a *** *****", "list": [1, -2.5e3, true, null, "***"], "nested": {"apple": {"text": "no"}} }"#);
+
+        // escapes are decoded before matching and kept outside the censored chars
+        assert_eq!(filter.try_filter_json(r#"["b\u0061d\n\"ok\"", "\u65e5\u672c!", "\ud83d\ude00 bad \u00e9"]"#, "*").unwrap(), r#"["***\n\"ok\"", "**!", "\ud83d\ude00 *** \u00e9"]"#);
+        assert_eq!(filter.try_filter_json(r#""bad""#, CensorStrategy::Token("\"x\\")).unwrap(), r#""\"x\\""#);
+        assert_eq!(filter.try_filter_json(r#""lone \udc00 bad""#, "*").unwrap(), r#""lone \udc00 ***""#);
+        assert_eq!(filter.try_filter_json(" [ ] ", "*").unwrap(), " [ ] ");
+        assert_eq!(filter.try_filter_json("{}", "*").unwrap(), "{}");
+
+        let deep = format!("{}\"bad\"{}", "[".repeat(100000), "]".repeat(100000));
+        assert_eq!(filter.try_filter_json(&deep, "*").unwrap(), deep.replace("bad", "***"));
+
+        let json = r#"{"user": {"name": "bad", "bio": "bad"}, "comments": [{"text": "bad", "id": "bad"}, {"text": "apple"}], "title": "bad"}"#;
+        assert_eq!(filter.try_filter_json_paths(json, "*", &["user.bio", "comments.*.text"]).unwrap(), r#"{"user": {"name": "bad", "bio": "***"}, "comments": [{"text": "***", "id": "bad"}, {"text": "*****"}], "title": "bad"}"#);
+        assert_eq!(filter.try_filter_json_paths(json, "*", &["user", "comments.1"]).unwrap(), r#"{"user": {"name": "***", "bio": "***"}, "comments": [{"text": "bad", "id": "bad"}, {"text": "*****"}], "title": "bad"}"#);
+        assert_eq!(filter.try_filter_json_paths(json, "*", &[]).unwrap(), json);
+
+        // a backslash escapes the dots and the stars of the keys
+        let json = r#"{"a.b": "bad", "a": {"b": "bad", "*": "bad", "c": "bad"}, "x\\y": "bad"}"#;
+        assert_eq!(filter.try_filter_json_paths(json, "*", &[r"a\.b"]).unwrap(), r#"{"a.b": "***", "a": {"b": "bad", "*": "bad", "c": "bad"}, "x\\y": "bad"}"#);
+        assert_eq!(filter.try_filter_json_paths(json, "*", &["a.b"]).unwrap(), r#"{"a.b": "bad", "a": {"b": "***", "*": "bad", "c": "bad"}, "x\\y": "bad"}"#);
+        assert_eq!(filter.try_filter_json_paths(json, "*", &[r"a.\*"]).unwrap(), r#"{"a.b": "bad", "a": {"b": "bad", "*": "***", "c": "bad"}, "x\\y": "bad"}"#);
+        assert_eq!(filter.try_filter_json_paths(json, "*", &[r"x\\y"]).unwrap(), r#"{"a.b": "bad", "a": {"b": "bad", "*": "bad", "c": "bad"}, "x\\y": "***"}"#);
+    }
+
+    #[test]
+    fn aho_corasick_json_error_tests() {
+        let mut filter = AhoCorasickFilter::new();
+        filter.build(vec!["bad"]);
+
+        let line_of = |json: &str| match filter.try_filter_json(json, "*") {
+            Err(Error::Parse { line, .. }) => Some(line),
+            _ => None,
+        };
+
+        assert_eq!(line_of("{\n\"a\": bad}"), Some(2));
+        assert_eq!(line_of("[1, 2,]"), Some(1));
+        assert_eq!(line_of("[\n1\n2]"), Some(3));
+        assert_eq!(line_of("{\"a\" 1}"), Some(1));
+        assert_eq!(line_of("{1: 2}"), Some(1));
+        assert_eq!(line_of("\"bad"), Some(1));
+        assert_eq!(line_of("\"\\x\""), Some(1));
+        assert_eq!(line_of("\"\\u12\""), Some(1));
+        assert_eq!(line_of("\"a\nb\""), Some(1));
+        assert_eq!(line_of("[01]"), Some(1));
+        assert_eq!(line_of("[1.]"), Some(1));
+        assert_eq!(line_of("[1] [2]"), Some(1));
+        assert_eq!(line_of("[tru]"), Some(1));
+        assert_eq!(line_of(""), Some(1));
+        assert_eq!(line_of("[\"bad\"]"), None);
+
+        // an invalid json is never returned unfiltered
+        assert!(filter.try_filter_json("[\"bad\",]", "*").is_err());
+        assert!(filter.try_filter_json_paths("{\"a\": bad}", "*", &["a"]).is_err());
+        assert_eq!(filter.try_filter_json("{\n\n\"a\": x}", "*").err().unwrap().to_string(), "line 3: expected a value, found 'x'");
+
+        // the empty path and an unknown escape are rejected instead of selecting everything
+        assert!(matches!(filter.try_filter_json_paths("[\"bad\"]", "*", &[""]), Err(Error::InvalidPath(path)) if path.is_empty()));
+        assert!(matches!(filter.try_filter_json_paths("[\"bad\"]", "*", &["0", r"a\b"]), Err(Error::InvalidPath(path)) if path == r"a\b"));
+        assert!(matches!(filter.try_filter_json_paths("[\"bad\"]", "*", &["a\\"]), Err(Error::InvalidPath(_))));
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use super::aho_corasick::AhoCorasick;
+use crate::error::Error;
+
+// a ⟦ of the text is masked as ⟦⟧ so the text never contains a placeholder, no placeholder contains ⟦⟧
+const ESCAPED_OPEN: &str = "⟦⟧";
+
+/**
+ * the originals of the placeholders of a masked text
+ * the placeholder of originals[k] is ⟦TERM_k+1⟧
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MaskMapping {
+    originals: Vec<String>,
+}
+
+impl MaskMapping {
+    pub fn placeholder(number: usize) -> String {
+        format!("⟦TERM_{}⟧", number)
+    }
+
+    pub fn len(&self) -> usize {
+        self.originals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+
+    /**
+     * returns the original text of the placeholder
+     */
+    pub fn original(&self, placeholder: &str) -> Option<&str> {
+        let number: usize = placeholder.strip_prefix("⟦TERM_")?.strip_suffix('⟧')?.parse().ok()?;
+        self.originals.get(number.checked_sub(1)?).map(|original| original.as_str())
+    }
+
+    /**
+     * returns tuples (placeholder, original) in the order of the placeholder numbers
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (String, &str)> {
+        self.originals.iter().enumerate().map(|(k, original)| (Self::placeholder(k + 1), original.as_str()))
+    }
+
+    /**
+     * replaces every placeholder of the mapping with its original wherever it is in the text and restores the ⟦ that were escaped as ⟦⟧
+     * placeholders can be moved, repeated or removed, text that is not a placeholder of the mapping is kept
+     * every placeholder starts with its only ⟦ so the text is read once without an automaton and unmask can not fail
+     */
+    pub fn unmask(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(open) = rest.find('⟦') {
+            output.push_str(&rest[..open]);
+            rest = &rest[open..];
+
+            if let Some(after) = rest.strip_prefix(ESCAPED_OPEN) {
+                output.push('⟦');
+                rest = after;
+                continue;
+            }
+
+            match self.placeholder_at(rest) {
+                Some((original, length)) => {
+                    output.push_str(original);
+                    rest = &rest[length..];
+                }
+                None => {
+                    output.push('⟦');
+                    rest = &rest['⟦'.len_utf8()..];
+                }
+            }
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /**
+     * returns the original and the byte length of the placeholder of the mapping at the start of the text
+     */
+    fn placeholder_at(&self, text: &str) -> Option<(&str, usize)> {
+        let digits = text.strip_prefix("⟦TERM_")?;
+        let length = digits.find(|c: char| !c.is_ascii_digit())?;
+
+        if !digits[length..].starts_with('⟧') {
+            return None;
+        }
+
+        let number: usize = digits[..length].parse().ok()?;
+        let placeholder = Self::placeholder(number);
+
+        // ⟦TERM_01⟧ is not the placeholder of the first original
+        if !text.starts_with(&placeholder) {
+            return None;
+        }
+
+        self.originals.get(number.checked_sub(1)?).map(|original| (original.as_str(), placeholder.len()))
+    }
+}
+
+impl AhoCorasick<char> {
+    /**
+     * replaces every match with a placeholder, ex: ⟦TERM_1⟧, and returns the masked string and the mapping that restores the originals
+     * overlapping matches are merged and masked once, the same original text always gets the same placeholder
+     * the placeholders are numbered from 1 in the order of their first match
+     * a ⟦ of the string is escaped as ⟦⟧ so a placeholder that is already in the string is kept as it is by unmask
+     * the automaton can be inconsistent after an external modification of the nodes, then the whole string is masked as ⟦TERM_1⟧
+     */
+    pub fn mask(&self, string: &str) -> (String, MaskMapping) {
+        self.try_mask(string).unwrap_or_else(|_| {
+            if string.is_empty() {
+                return (String::new(), MaskMapping::default());
+            }
+
+            (MaskMapping::placeholder(1), MaskMapping { originals: vec![string.to_string()] })
+        })
+    }
+
+    pub fn try_mask(&self, string: &str) -> Result<(String, MaskMapping), Error> {
+        let mut matches: Vec<(usize, usize)> = self.try_search(string)?
+            .into_iter()
+            .filter(|&(_, length)| length != 0)
+            .map(|(index, length)| (index, index + length))
+            .collect();
+        matches.sort_unstable();
+
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+        for (start, end) in matches {
+            match ranges.last_mut() {
+                Some(last) if start < last.1 => last.1 = last.1.max(end),
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        let characters: Vec<char> = string.chars().collect();
+        let mut mapping = MaskMapping::default();
+        let mut numbers: HashMap<String, usize> = HashMap::new();
+        let mut output = String::with_capacity(string.len());
+        let mut i = 0;
+
+        for (start, end) in ranges {
+            let original: String = characters[start..end].iter().collect();
+            let number = *numbers.entry(original.clone()).or_insert_with(|| {
+                mapping.originals.push(original);
+                mapping.originals.len()
+            });
+
+            escape(&characters[i..start], &mut output);
+            output.push_str(&MaskMapping::placeholder(number));
+            i = end;
+        }
+
+        escape(&characters[i..], &mut output);
+        Ok((output, mapping))
+    }
+}
+
+fn escape(characters: &[char], output: &mut String) {
+    for &c in characters {
+        match c {
+            '⟦' => output.push_str(ESCAPED_OPEN),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aho_corasick_mask_tests() {
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["alice", "bob", "bobby", "555-1234", "日本", ""]);
+
+        let (masked, mapping) = aho_corasick.mask("alice called bobby at 555-1234, then alice called bob");
+        assert_eq!(masked, "⟦TERM_1⟧ called ⟦TERM_2⟧ at ⟦TERM_3⟧, then ⟦TERM_1⟧ called ⟦TERM_4⟧");
+        assert_eq!(mapping.len(), 4);
+        assert_eq!(mapping.original("⟦TERM_2⟧"), Some("bobby"));
+        assert_eq!(mapping.original("⟦TERM_5⟧"), None);
+        assert_eq!(mapping.original("⟦TERM_0⟧"), None);
+        assert_eq!(mapping.iter().map(|(placeholder, original)| format!("{}={}", placeholder, original)).collect::<Vec<String>>(), vec!["⟦TERM_1⟧=alice", "⟦TERM_2⟧=bobby", "⟦TERM_3⟧=555-1234", "⟦TERM_4⟧=bob"]);
+        assert_eq!(mapping.unmask(&masked), "alice called bobby at 555-1234, then alice called bob");
+
+        // the placeholders can be moved, repeated and removed
+        assert_eq!(mapping.unmask("⟦TERM_4⟧ and ⟦TERM_1⟧⟦TERM_1⟧ ⟦TERM_9⟧ ⟦TERM_"), "bob and alicealice ⟦TERM_9⟧ ⟦TERM_");
+
+        let (masked, mapping) = aho_corasick.mask("日本の bob");
+        assert_eq!(masked, "⟦TERM_1⟧の ⟦TERM_2⟧");
+        assert_eq!(mapping.unmask(&masked), "日本の bob");
+
+        let (masked, mapping) = aho_corasick.mask("nothing here");
+        assert_eq!(masked, "nothing here");
+        assert!(mapping.is_empty());
+        assert_eq!(mapping.unmask("⟦TERM_1⟧"), "⟦TERM_1⟧");
+
+        // a placeholder that is already in the text is escaped and restored as it is
+        let (masked, mapping) = aho_corasick.mask("bob wrote ⟦TERM_1⟧ literally");
+        assert_eq!(masked, "⟦TERM_1⟧ wrote ⟦⟧TERM_1⟧ literally");
+        assert_eq!(mapping.unmask(&masked), "bob wrote ⟦TERM_1⟧ literally");
+
+        let (masked, mapping) = aho_corasick.mask("⟦⟧ ⟦⟦TERM_1⟧⟧ alice⟦");
+        assert_eq!(masked, "⟦⟧⟧ ⟦⟧⟦⟧TERM_1⟧⟧ ⟦TERM_1⟧⟦⟧");
+        assert_eq!(mapping.unmask(&masked), "⟦⟧ ⟦⟦TERM_1⟧⟧ alice⟦");
+    }
+
+    #[test]
+    fn aho_corasick_mask_many_tests() {
+        let words: Vec<String> = (0..12).map(|k| format!("word{}x", k)).collect();
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(words.iter().map(|word| word.as_str()).collect());
+
+        let text = words.join(" ");
+        let (masked, mapping) = aho_corasick.mask(&text);
+        assert_eq!(mapping.len(), 12);
+        assert!(masked.contains("⟦TERM_1⟧ ⟦TERM_2⟧") && masked.ends_with("⟦TERM_11⟧ ⟦TERM_12⟧"));
+        assert_eq!(mapping.unmask(&masked), text);
+        assert_eq!(mapping.unmask("⟦TERM_01⟧ ⟦TERM_12⟧⟦TERM_13⟧ ⟦TERM_1"), "⟦TERM_01⟧ word11x⟦TERM_13⟧ ⟦TERM_1");
+    }
+
+    #[test]
+    fn aho_corasick_mask_fail_closed_tests() {
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec!["alice", "bob"]);
+
+        let a_node = *aho_corasick.nodes.get(&aho_corasick.root).unwrap().children.get(&'a').unwrap();
+        aho_corasick.nodes.get_mut(&a_node).unwrap().suffix_link = None;
+        assert!(aho_corasick.try_mask("alice and bob").is_err());
+
+        // nothing of the string is exposed when the automaton is inconsistent
+        let (masked, mapping) = aho_corasick.mask("alice and bob");
+        assert_eq!(masked, "⟦TERM_1⟧");
+        assert_eq!(mapping.unmask(&masked), "alice and bob");
+        assert_eq!(aho_corasick.mask(""), (String::new(), MaskMapping::default()));
+    }
+}
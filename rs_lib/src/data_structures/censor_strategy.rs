@@ -0,0 +1,163 @@
+/**
+ * how a match is replaced by AhoCorasickFilter
+ * a &str converts to Repeat so the filter functions can still be called with a censored string
+ *
+ * ignored chars inside a match are kept by the per char strategies (Repeat, KeepEnds, Grawlix)
+ * the whole match strategies (Token, FixedWidth, Custom) replace the match including its ignored chars
+ */
+pub enum CensorStrategy<'a> {
+    /// every censored char is replaced by the string
+    Repeat(&'a str),
+    /// the first and last censored chars are kept and the chars between are replaced by the string, ex: a***e
+    /// matches with 2 or less censored chars are fully replaced
+    KeepEnds(&'a str),
+    /// every censored char is replaced by the next char of the string, cycling back to the start of the string for each match, ex: @#$%!
+    /// an empty string censors with * so a match is never removed without a trace
+    Grawlix(&'a str),
+    /// the whole match is replaced by the token once, ex: [removed]
+    Token(&'a str),
+    /// the whole match is replaced by the string repeated a fixed number of times so the length of the match is hidden
+    FixedWidth(&'a str, usize),
+    /// the whole match is replaced by the return value of the function, the function receives the original text of the match
+    Custom(Box<dyn Fn(&str) -> String + Send + Sync + 'a>),
+}
+
+impl<'a> From<&'a str> for CensorStrategy<'a> {
+    fn from(censored_string: &'a str) -> Self {
+        CensorStrategy::Repeat(censored_string)
+    }
+}
+
+impl<'a> CensorStrategy<'a> {
+    pub fn custom<F: Fn(&str) -> String + Send + Sync + 'a>(function: F) -> Self {
+        CensorStrategy::Custom(Box::new(function))
+    }
+
+    /**
+     * appends the replacement of the matched chars to the output
+     */
+    pub(crate) fn censor<F: Fn(char) -> bool>(&self, matched: &[char], is_ignored: F, output: &mut String) {
+        match self {
+            CensorStrategy::Repeat(censored_string) => {
+                for &c in matched {
+                    if is_ignored(c) {
+                        output.push(c);
+                    }
+                    else {
+                        output.push_str(censored_string);
+                    }
+                }
+            }
+            CensorStrategy::KeepEnds(censored_string) => {
+                let censored_count = matched.iter().filter(|&&c| !is_ignored(c)).count();
+                let mut k = 0;
+
+                for &c in matched {
+                    if is_ignored(c) {
+                        output.push(c);
+                        continue;
+                    }
+
+                    if censored_count > 2 && (k == 0 || k == censored_count - 1) {
+                        output.push(c);
+                    }
+                    else {
+                        output.push_str(censored_string);
+                    }
+
+                    k += 1;
+                }
+            }
+            CensorStrategy::Grawlix(symbols) => {
+                let symbols = if symbols.is_empty() { "*" } else { symbols };
+                let mut cycle = symbols.chars().cycle();
+
+                for &c in matched {
+                    if is_ignored(c) {
+                        output.push(c);
+                    }
+                    else if let Some(symbol) = cycle.next() {
+                        output.push(symbol);
+                    }
+                }
+            }
+            CensorStrategy::Token(token) => {
+                output.push_str(token);
+            }
+            CensorStrategy::FixedWidth(censored_string, width) => {
+                output.push_str(&censored_string.repeat(*width));
+            }
+            CensorStrategy::Custom(function) => {
+                output.push_str(&function(&matched.iter().collect::<String>()));
+            }
+        }
+    }
+}
+
+/**
+ * how a match is wrapped by the highlight functions of AhoCorasickFilter
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Markup<'a> {
+    /// wraps matches in <mark></mark> and html escapes all of the text
+    Html,
+    /// wraps matches in <mark class="..."></mark> and html escapes all of the text
+    HtmlClass(&'a str),
+    /// wraps matches in the ansi select graphic rendition escape with the parameters, ex: "1;31" for bold red, and resets after the match
+    Ansi(&'a str),
+    /// wraps matches in the prefix and suffix, the text is not escaped
+    Custom(&'a str, &'a str),
+}
+
+impl Markup<'_> {
+    pub(crate) fn open(&self, output: &mut String) {
+        match self {
+            Markup::Html => output.push_str("<mark>"),
+            Markup::HtmlClass(class) => {
+                output.push_str("<mark class=\"");
+                escape_html(class, output);
+                output.push_str("\">");
+            }
+            Markup::Ansi(parameters) => {
+                output.push_str("\x1b[");
+                output.push_str(parameters);
+                output.push('m');
+            }
+            Markup::Custom(prefix, _) => output.push_str(prefix),
+        }
+    }
+
+    pub(crate) fn close(&self, output: &mut String) {
+        match self {
+            Markup::Html | Markup::HtmlClass(_) => output.push_str("</mark>"),
+            Markup::Ansi(_) => output.push_str("\x1b[0m"),
+            Markup::Custom(_, suffix) => output.push_str(suffix),
+        }
+    }
+
+    /**
+     * appends the text, escaped when the markup is html
+     */
+    pub(crate) fn text(&self, text: &str, output: &mut String) {
+        match self {
+            Markup::Html | Markup::HtmlClass(_) => escape_html(text, output),
+            Markup::Ansi(_) | Markup::Custom(_, _) => output.push_str(text),
+        }
+    }
+}
+
+/**
+ * appends the text with & < > " ' replaced by their html entities
+ */
+pub fn escape_html(text: &str, output: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(c),
+        }
+    }
+}
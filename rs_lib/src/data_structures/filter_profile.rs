@@ -0,0 +1,460 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::path::Path;
+use super::aho_corasick_filter::AhoCorasickFilter;
+use super::aho_corasick_filter::CaseMatching;
+use super::filter_term::Category;
+use super::filter_term::Severity;
+use super::filter_term::TermInfo;
+use super::filter_term::check_weight;
+use super::ignore_chars::IgnoreChars;
+use super::ignore_chars::IgnoreClass;
+use super::normalization::Normalization;
+use crate::error::Error;
+
+/**
+ * a filter and the settings of a profile file
+ *
+ * profile format:
+ * a line starting with # is a comment, blank lines are skipped, every line is trimmed
+ * a backslash makes the next char literal, an escaped char is never trimmed and does not start a comment or a section, split a column or end a key
+ * ex: \#1, a\|b, \ padded\ , c:\\dir, a backslash at the end of a line is an error
+ * the lines before the first section are terms
+ *
+ * [terms]
+ * one term per line, optionally followed by | category | severity | weight, empty columns keep the default
+ * the weight is a finite number of at least 0
+ * ex: apple | profanity | high | 2.5
+ *
+ * [allow]
+ * one allowed word per line
+ *
+ * [settings]
+ * key = value lines
+ * replacement = the string that replaces every censored char, * by default
+ * ignore = every char of the value is ignored
+ * ignore_classes = comma separated whitespace, punctuation, format, combining_mark
+ * max_gap = the max number of ignored chars between two chars of a match
+ * case = sensitive, ascii or insensitive
+ * normalization = comma separated compatibility, case_fold, strip_diacritics
+ * collapse_repeats, leetspeak, strip_invisible, skip_invisible = true or false
+ * a key can be set once, a second line with the same key is an error
+ */
+pub struct FilterProfile {
+    pub filter: AhoCorasickFilter,
+    pub replacement: String,
+    pub ignore_chars: HashSet<char>,
+    pub ignore_classes: Vec<IgnoreClass>,
+    pub max_gap: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Terms,
+    Allow,
+    Settings,
+}
+
+impl FilterProfile {
+    /**
+     * a profile of the filter with the default settings, * as the replacement and no ignored chars
+     */
+    pub fn new(filter: AhoCorasickFilter) -> Self {
+        Self {
+            filter,
+            replacement: "*".to_string(),
+            ignore_chars: HashSet::new(),
+            ignore_classes: Vec::new(),
+            max_gap: None,
+        }
+    }
+
+    /**
+     * returns the ignored chars of the profile
+     */
+    pub fn ignore(&self) -> IgnoreChars<'static> {
+        let mut ignore_chars = IgnoreChars::new().chars(self.ignore_chars.iter().copied());
+
+        for &class in &self.ignore_classes {
+            ignore_chars = ignore_chars.class(class);
+        }
+
+        match self.max_gap {
+            Some(max_gap) => ignore_chars.max_gap(max_gap),
+            None => ignore_chars,
+        }
+    }
+
+    /**
+     * filters the string with the replacement and the ignored chars of the profile
+     */
+    pub fn filter(&self, string: &str) -> String {
+        self.filter.filter_and_ignore_chars(string, self.replacement.as_str(), self.ignore())
+    }
+
+    pub fn try_filter(&self, string: &str) -> Result<String, Error> {
+        self.filter.try_filter_and_ignore_chars(string, self.replacement.as_str(), self.ignore())
+    }
+
+    /**
+     * reads a profile, the errors of the profile are Error::Parse with the line number starting at 1
+     * a line that is not valid utf-8 is an error of the profile too
+     */
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
+        let mut profile = FilterProfile::new(AhoCorasickFilter::new());
+
+        // the words are inserted after every setting is read because the normalization changes the patterns
+        let mut terms: Vec<(usize, String, TermInfo)> = Vec::new();
+        let mut allowed: Vec<(usize, String)> = Vec::new();
+        let mut section = Section::Terms;
+        let mut keys: HashSet<String> = HashSet::new();
+
+        for (index, line) in BufReader::new(reader).lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|error| match error.kind() {
+                ErrorKind::InvalidData => parse_error(line_number, "invalid utf-8".to_string()),
+                _ => Error::Io(error),
+            })?;
+            let line = unescape(&line).map_err(|message| parse_error(line_number, message))?;
+            let line = trim(&line);
+
+            if line.is_empty() || line[0] == ('#', false) {
+                continue;
+            }
+
+            if let [('[', false), name @ .., (']', false)] = line {
+                section = match text(trim(name)).as_str() {
+                    "terms" => Section::Terms,
+                    "allow" => Section::Allow,
+                    "settings" => Section::Settings,
+                    name => return Err(parse_error(line_number, format!("unknown section [{}]", name))),
+                };
+
+                continue;
+            }
+
+            match section {
+                Section::Terms => {
+                    let (term, info) = parse_term(line).map_err(|message| parse_error(line_number, message))?;
+                    terms.push((line_number, term, info));
+                }
+                Section::Allow => allowed.push((line_number, text(line))),
+                Section::Settings => profile.apply_setting(line, &mut keys).map_err(|message| parse_error(line_number, message))?,
+            }
+        }
+
+        for (line_number, term, info) in terms {
+            profile.filter.try_insert_with(&term, info).map_err(|error| parse_error(line_number, error.to_string()))?;
+        }
+
+        for (line_number, word) in allowed {
+            profile.filter.try_insert_allowed(&word).map_err(|error| parse_error(line_number, error.to_string()))?;
+        }
+
+        Ok(profile)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    /**
+     * keys are the keys that are already set, a key that is set again is an error
+     */
+    fn apply_setting(&mut self, line: &[(char, bool)], keys: &mut HashSet<String>) -> Result<(), String> {
+        let separator = line.iter().position(|&c| c == ('=', false)).ok_or_else(|| format!("expected key = value, found \"{}\"", text(line)))?;
+        let key = text(trim(&line[..separator]));
+        let value = text(trim(&line[separator + 1..]));
+        let value = value.as_str();
+
+        if keys.contains(&key) {
+            return Err(format!("setting \"{}\" is already set", key));
+        }
+
+        match key.as_str() {
+            "replacement" => self.replacement = value.to_string(),
+            "ignore" => self.ignore_chars.extend(value.chars()),
+            "ignore_classes" => {
+                for name in list(value) {
+                    let class = match name {
+                        "whitespace" => IgnoreClass::Whitespace,
+                        "punctuation" => IgnoreClass::Punctuation,
+                        "format" => IgnoreClass::Format,
+                        "combining_mark" => IgnoreClass::CombiningMark,
+                        _ => return Err(format!("unknown ignore class \"{}\"", name)),
+                    };
+
+                    if !self.ignore_classes.contains(&class) {
+                        self.ignore_classes.push(class);
+                    }
+                }
+            }
+            "max_gap" => self.max_gap = Some(value.parse().map_err(|_| format!("invalid max gap \"{}\"", value))?),
+            "case" => self.filter.try_set_case_matching(match value {
+                "sensitive" => CaseMatching::Sensitive,
+                "ascii" => CaseMatching::AsciiInsensitive,
+                "insensitive" => CaseMatching::Insensitive,
+                _ => return Err(format!("unknown case matching \"{}\"", value)),
+            }).map_err(|error| error.to_string())?,
+            "normalization" => {
+                let mut normalization = Normalization::default();
+
+                for name in list(value) {
+                    match name {
+                        "compatibility" => normalization.compatibility = true,
+                        "case_fold" => normalization.case_fold = true,
+                        "strip_diacritics" => normalization.strip_diacritics = true,
+                        _ => return Err(format!("unknown normalization \"{}\"", name)),
+                    }
+                }
+
+                self.filter.try_set_normalization(normalization).map_err(|error| error.to_string())?;
+            }
+            "collapse_repeats" => self.filter.set_collapse_repeats(parse_bool(value)?),
+            "leetspeak" => {
+                if parse_bool(value)? {
+                    self.filter.add_leetspeak_equivalences();
+                }
+            }
+            "strip_invisible" => self.filter.set_strip_invisible(parse_bool(value)?),
+            "skip_invisible" => self.filter.set_skip_invisible(parse_bool(value)?),
+            key => return Err(format!("unknown setting \"{}\"", key)),
+        }
+
+        keys.insert(key);
+        Ok(())
+    }
+}
+
+impl From<AhoCorasickFilter> for FilterProfile {
+    fn from(filter: AhoCorasickFilter) -> Self {
+        FilterProfile::new(filter)
+    }
+}
+
+impl AhoCorasickFilter {
+    /**
+     * loads a profile file, see FilterProfile for the format
+     */
+    pub fn from_profile<P: AsRef<Path>>(path: P) -> Result<FilterProfile, Error> {
+        FilterProfile::from_file(path)
+    }
+
+    pub fn from_profile_reader<R: Read>(reader: R) -> Result<FilterProfile, Error> {
+        FilterProfile::from_reader(reader)
+    }
+}
+
+fn parse_error(line: usize, message: String) -> Error {
+    Error::Parse { line, message }
+}
+
+/**
+ * returns the chars of the line and whether each one was escaped by a backslash
+ */
+fn unescape(line: &str) -> Result<Vec<(char, bool)>, String> {
+    let mut characters = Vec::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            characters.push((chars.next().ok_or("a backslash at the end of the line escapes nothing")?, true));
+        }
+        else {
+            characters.push((c, false));
+        }
+    }
+
+    Ok(characters)
+}
+
+/**
+ * removes the whitespace at both ends that is not escaped
+ */
+fn trim(characters: &[(char, bool)]) -> &[(char, bool)] {
+    let is_trimmed = |&(c, escaped): &(char, bool)| c.is_whitespace() && !escaped;
+    let start = characters.iter().position(|c| !is_trimmed(c)).unwrap_or(characters.len());
+    let end = characters.iter().rposition(|c| !is_trimmed(c)).map_or(start, |end| end + 1);
+
+    &characters[start..end]
+}
+
+fn text(characters: &[(char, bool)]) -> String {
+    characters.iter().map(|&(c, _)| c).collect()
+}
+
+/**
+ * returns the non empty trimmed items of a comma separated list
+ */
+fn list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(|item| item.trim()).filter(|item| !item.is_empty())
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("expected true or false, found \"{}\"", value)),
+    }
+}
+
+fn parse_term(line: &[(char, bool)]) -> Result<(String, TermInfo), String> {
+    let columns: Vec<String> = line.split(|&c| c == ('|', false)).map(|column| text(trim(column))).collect();
+    let mut columns = columns.iter().map(|column| column.as_str());
+    let term = columns.next().unwrap_or_default();
+
+    if term.is_empty() {
+        return Err("empty term".to_string());
+    }
+
+    let mut info = TermInfo::default();
+
+    if let Some(category) = columns.next().filter(|column| !column.is_empty()) {
+        info.category = match category.to_lowercase().as_str() {
+            "general" => Category::General,
+            "slur" => Category::Slur,
+            "profanity" => Category::Profanity,
+            "spam" => Category::Spam,
+            "pii" => Category::Pii,
+            _ => return Err(format!("unknown category \"{}\"", category)),
+        };
+    }
+
+    if let Some(severity) = columns.next().filter(|column| !column.is_empty()) {
+        info.severity = match severity.to_lowercase().as_str() {
+            "low" => Severity::Low,
+            "medium" => Severity::Medium,
+            "high" => Severity::High,
+            "critical" => Severity::Critical,
+            _ => return Err(format!("unknown severity \"{}\"", severity)),
+        };
+    }
+
+    if let Some(weight) = columns.next().filter(|column| !column.is_empty()) {
+        let weight = weight.parse().map_err(|_| format!("invalid weight \"{}\"", weight))?;
+        info.weight = check_weight(weight).map_err(|error| error.to_string())?;
+    }
+
+    if columns.next().is_some() {
+        return Err("too many columns, expected term | category | severity | weight".to_string());
+    }
+
+    Ok((term.to_string(), info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const PROFILE: &str = "\
+# blocked words
+apple
+bat | profanity | high | 2.5
+
+[allow]
+pineapple
+
+[settings]
+replacement = #
+ignore = ._
+ignore_classes = whitespace, format
+max_gap = 2
+case = insensitive
+normalization = compatibility, strip_diacritics
+
+[terms]
+  café   | spam
+badword | | critical
+";
+
+    #[test]
+    fn filter_profile_tests() {
+        let profile = AhoCorasickFilter::from_profile_reader(PROFILE.as_bytes()).unwrap();
+
+        assert_eq!(profile.replacement, "#");
+        assert_eq!(profile.ignore_chars, HashSet::from(['.', '_']));
+        assert_eq!(profile.ignore_classes, vec![IgnoreClass::Whitespace, IgnoreClass::Format]);
+        assert_eq!(profile.max_gap, Some(2));
+
+        assert_eq!(profile.filter("an APPLE, a b.a_t, a pineapple"), "an #####, a #.#_#, a pineapple");
+        assert_eq!(profile.filter("a p p l e, a p    p l e"), "# # # # #, a p    p l e");
+        assert_eq!(profile.filter("ＣＡＦＥ, a bad word"), "####, a ### ####");
+
+        assert_eq!(profile.filter.term_info("bat"), Some(TermInfo::new(Category::Profanity, Severity::High).with_weight(2.5)));
+        assert_eq!(profile.filter.term_info("cafe"), Some(TermInfo::new(Category::Spam, Severity::Low)));
+        assert_eq!(profile.filter.term_info("badword"), Some(TermInfo::new(Category::General, Severity::Critical)));
+        assert_eq!(profile.filter.term_info("apple"), Some(TermInfo::default()));
+
+        let empty = AhoCorasickFilter::from_profile_reader("".as_bytes()).unwrap();
+        assert_eq!(empty.filter("apple"), "apple");
+        assert_eq!(empty.replacement, "*");
+
+        let path = std::env::temp_dir().join(format!("rs_lib_filter_profile_test_{}.txt", std::process::id()));
+        File::create(&path).unwrap().write_all(PROFILE.as_bytes()).unwrap();
+        let profile = AhoCorasickFilter::from_profile(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(profile.filter("apple"), "#####");
+
+        assert!(matches!(AhoCorasickFilter::from_profile(std::env::temp_dir().join("rs_lib_missing_profile.txt")), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn filter_profile_error_tests() {
+        let line_of = |profile: &str| match AhoCorasickFilter::from_profile_reader(profile.as_bytes()) {
+            Err(Error::Parse { line, .. }) => Some(line),
+            _ => None,
+        };
+
+        assert_eq!(line_of("apple\nbat | rude"), Some(2));
+        assert_eq!(line_of("apple | general | extreme"), Some(1));
+        assert_eq!(line_of("apple | general | low | heavy"), Some(1));
+        assert_eq!(line_of("apple | general | low | 1 | extra"), Some(1));
+        assert_eq!(line_of("# comment\n\n   | spam"), Some(3));
+        assert_eq!(line_of("[blocked]\napple"), Some(1));
+        assert_eq!(line_of("[settings]\nreplacement = *\nmax_gap = -1"), Some(3));
+        assert_eq!(line_of("[settings]\ncase"), Some(2));
+        assert_eq!(line_of("[settings]\ncollapse_repeats = yes"), Some(2));
+        assert_eq!(line_of("[settings]\ncolor = red"), Some(2));
+        assert_eq!(line_of("[settings]\nignore_classes = whitespace, emoji"), Some(2));
+        assert_eq!(line_of("[settings]\nnormalization = nfc"), Some(2));
+        assert_eq!(line_of("apple\n[allow]\npineapple"), None);
+        assert_eq!(line_of("apple | general | low | NaN"), Some(1));
+        assert_eq!(line_of("apple\nbat | general | low | inf"), Some(2));
+        assert_eq!(line_of("apple | | | -1"), Some(1));
+        assert_eq!(line_of("apple\\"), Some(1));
+        assert_eq!(line_of("[settings]\nleetspeak = true\nleetspeak = false"), Some(3));
+        assert_eq!(line_of("[settings]\nignore = .\nreplacement = #\nignore = -"), Some(4));
+
+        let invalid = AhoCorasickFilter::from_profile_reader(&b"apple\nbat\xff\ncafe"[..]).err().unwrap();
+        assert!(matches!(invalid, Error::Parse { line: 2, .. }));
+        assert_eq!(invalid.to_string(), "line 2: invalid utf-8");
+
+        let error = AhoCorasickFilter::from_profile_reader("[settings]\nleetspeak = true\nleetspeak = false".as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "line 3: setting \"leetspeak\" is already set");
+
+        let error = AhoCorasickFilter::from_profile_reader("[settings]\ncase = upper".as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "line 2: unknown case matching \"upper\"");
+
+        let error = AhoCorasickFilter::from_profile_reader("apple | | | -1".as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "line 1: weight -1 is not a finite number of at least 0");
+    }
+
+    #[test]
+    fn filter_profile_escape_tests() {
+        let profile = AhoCorasickFilter::from_profile_reader("\\#1\na\\|b | spam\n\\ x\\ \n\\[c]\nd\\\\e\n[settings]\nreplacement = \\=\\ \nignore = \\#\\ ".as_bytes()).unwrap();
+
+        assert_eq!(profile.replacement, "= ");
+        assert_eq!(profile.ignore_chars, HashSet::from(['#', ' ']));
+        assert_eq!(profile.filter.term_info("#1"), Some(TermInfo::default()));
+        assert_eq!(profile.filter.term_info("a|b"), Some(TermInfo::new(Category::Spam, Severity::Low)));
+        assert_eq!(profile.filter.term_info(" x "), Some(TermInfo::default()));
+        assert_eq!(profile.filter.term_info("[c]"), Some(TermInfo::default()));
+        assert_eq!(profile.filter.term_info("d\\e"), Some(TermInfo::default()));
+        assert_eq!(profile.filter("a|b"), "= = = ");
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use super::normalization::NormalizationRule;
+use crate::error::Error;
+
+/**
+ * what kind of term a pattern of AhoCorasickFilter is
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Category {
+    #[default]
+    General,
+    Slur,
+    Profanity,
+    Spam,
+    Pii,
+}
+
+/**
+ * severities are ordered from Low to Critical
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Severity {
+    #[default]
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/**
+ * the tags of a pattern of AhoCorasickFilter
+ * the weight is used by the scoring, the default weight is 1, a weight has to be finite and at least 0
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TermInfo {
+    pub category: Category,
+    pub severity: Severity,
+    pub weight: f64,
+}
+
+impl Default for TermInfo {
+    fn default() -> Self {
+        Self { category: Category::default(), severity: Severity::default(), weight: 1.0 }
+    }
+}
+
+impl TermInfo {
+    pub fn new(category: Category, severity: Severity) -> Self {
+        Self { category, severity, ..Self::default() }
+    }
+
+    /**
+     * a weight that is NaN, infinite or negative is skipped and the current weight is kept
+     */
+    pub fn with_weight(self, weight: f64) -> Self {
+        self.try_with_weight(weight).unwrap_or(self)
+    }
+
+    pub fn try_with_weight(mut self, weight: f64) -> Result<Self, Error> {
+        self.weight = check_weight(weight)?;
+        Ok(self)
+    }
+}
+
+/**
+ * returns the weight if it is finite and at least 0
+ */
+pub(crate) fn check_weight(weight: f64) -> Result<f64, Error> {
+    if weight.is_finite() && weight >= 0.0 {
+        Ok(weight)
+    }
+    else {
+        Err(Error::InvalidWeight(weight))
+    }
+}
+
+/**
+ * a match of a pattern that was not allowed
+ * start and end are char indices of the text, end is exclusive
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermHit {
+    pub term: String,
+    pub info: TermInfo,
+    pub start: usize,
+    pub end: usize,
+}
+
+/**
+ * every pattern match of a message and the highest severity of every category that was hit
+ * invisible has the char indices of the default ignorable chars of the message, ex: zero width space
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FilterReport {
+    pub hits: Vec<TermHit>,
+    pub categories: HashMap<Category, Severity>,
+    pub invisible: Vec<usize>,
+}
+
+impl FilterReport {
+    pub(crate) fn add(&mut self, hit: TermHit) {
+        let severity = self.categories.entry(hit.info.category).or_insert(hit.info.severity);
+        *severity = (*severity).max(hit.info.severity);
+        self.hits.push(hit);
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.categories.values().copied().max()
+    }
+
+    pub fn has_invisible(&self) -> bool {
+        !self.invisible.is_empty()
+    }
+
+    pub fn has_category(&self, category: Category) -> bool {
+        self.categories.contains_key(&category)
+    }
+}
+
+/**
+ * a range of censored chars
+ * start and end are char indices, byte_start and byte_end are byte indices of the same chars, both ends are exclusive
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CensorSpan {
+    pub start: usize,
+    pub end: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
+/**
+ * how the weights of the matches of a message are combined into a score
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreAggregation {
+    /// the weights of every match are added, overlapping matches are all counted
+    #[default]
+    Sum,
+    /// the weight of the heaviest match
+    Max,
+    /// the weights are added but every term is counted at most the cap times
+    CappedPerTerm(usize),
+}
+
+/**
+ * per_chars normalizes the score by the length of the message, the score becomes the score per that many chars
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScoreOptions {
+    pub aggregation: ScoreAggregation,
+    pub per_chars: Option<usize>,
+}
+
+/**
+ * a term that was matched in a scored message
+ * contribution is the part of the score from the term before the length normalization
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermScore {
+    pub term: String,
+    pub weight: f64,
+    pub count: usize,
+    pub contribution: f64,
+}
+
+/**
+ * the score of a message and every term that was matched in the order of their first match
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Score {
+    pub value: f64,
+    pub terms: Vec<TermScore>,
+}
+
+impl Score {
+    /**
+     * length is the number of chars of the message
+     * returns an error if the weight of a hit is NaN, infinite or negative
+     */
+    pub(crate) fn new(hits: &[TermHit], length: usize, options: ScoreOptions) -> Result<Self, Error> {
+        let mut terms: Vec<TermScore> = Vec::new();
+        let mut term_indices: HashMap<&str, usize> = HashMap::new();
+
+        for hit in hits {
+            check_weight(hit.info.weight)?;
+
+            match term_indices.get(hit.term.as_str()) {
+                Some(&index) => terms[index].count += 1,
+                None => {
+                    term_indices.insert(&hit.term, terms.len());
+                    terms.push(TermScore { term: hit.term.clone(), weight: hit.info.weight, count: 1, contribution: 0.0 });
+                }
+            }
+        }
+
+        match options.aggregation {
+            ScoreAggregation::Sum => {
+                for term in &mut terms {
+                    term.contribution = term.weight * term.count as f64;
+                }
+            }
+            ScoreAggregation::Max => {
+                // the first of the heaviest terms is the only contribution
+                let heaviest = terms.iter().enumerate().fold(None, |heaviest: Option<(usize, f64)>, (index, term)| match heaviest {
+                    Some((_, weight)) if weight >= term.weight => heaviest,
+                    _ => Some((index, term.weight)),
+                });
+
+                if let Some((index, weight)) = heaviest {
+                    terms[index].contribution = weight;
+                }
+            }
+            ScoreAggregation::CappedPerTerm(cap) => {
+                for term in &mut terms {
+                    term.contribution = term.weight * term.count.min(cap) as f64;
+                }
+            }
+        }
+
+        let mut value: f64 = terms.iter().map(|term| term.contribution).sum();
+
+        if let Some(per_chars) = options.per_chars {
+            value = value * per_chars as f64 / length.max(1) as f64;
+        }
+
+        Ok(Self { value, terms })
+    }
+}
+
+/**
+ * why a censored span was censored
+ * original is the text of the span, terms are the words that matched inside the span in the order of their first match
+ * skipped are the char indices and chars that were skipped when matching, rules are the normalizations that changed a char of the span
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainedSpan {
+    pub span: CensorSpan,
+    pub original: String,
+    pub terms: Vec<String>,
+    pub skipped: Vec<(usize, char)>,
+    pub rules: Vec<NormalizationRule>,
+}
+
+/**
+ * the filtered string and the explanation of every censored span
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FilterExplanation {
+    pub output: String,
+    pub spans: Vec<ExplainedSpan>,
+}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+/**
+ * built in classes of chars that can be ignored by AhoCorasickFilter
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IgnoreClass {
+    /// chars with the unicode White_Space property
+    Whitespace,
+    /// ascii punctuation, the general punctuation block and common latin-1 and cjk punctuation
+    Punctuation,
+    /// invisible formatting chars, ex: zero width space, zero width joiner, soft hyphen, bidi controls
+    Format,
+    /// the combining diacritical mark blocks, ex: U+0301
+    CombiningMark,
+}
+
+impl IgnoreClass {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            IgnoreClass::Whitespace => c.is_whitespace(),
+            IgnoreClass::Punctuation => c.is_ascii_punctuation() || matches!(c,
+                '¡' | '§' | '«' | '¶' | '·' | '»' | '¿' |
+                '\u{2010}'..='\u{2027}' | '\u{2030}'..='\u{205e}' |
+                '\u{3001}'..='\u{3003}' | '\u{3008}'..='\u{3011}' | '\u{30fb}' |
+                '\u{ff01}'..='\u{ff0f}' | '\u{ff1a}'..='\u{ff20}'),
+            IgnoreClass::Format => matches!(c,
+                '\u{ad}' | '\u{34f}' | '\u{61c}' | '\u{180e}' |
+                '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{206f}' |
+                '\u{feff}' | '\u{fff9}'..='\u{fffb}' | '\u{e0000}'..='\u{e007f}'),
+            IgnoreClass::CombiningMark => matches!(c,
+                '\u{300}'..='\u{36f}' | '\u{1ab0}'..='\u{1aff}' | '\u{1dc0}'..='\u{1dff}' |
+                '\u{20d0}'..='\u{20ff}' | '\u{fe20}'..='\u{fe2f}'),
+        }
+    }
+}
+
+/**
+ * which chars AhoCorasickFilter skips when matching
+ * a char is ignored when it is one of the chars, in one of the classes or the predicate returns true for it
+ * a HashSet<char> converts to IgnoreChars so the ignore functions can still be called with a set
+ *
+ * max_gap limits the number of ignored chars between two chars of a match, a longer gap ends every match in progress
+ */
+#[derive(Default)]
+pub struct IgnoreChars<'a> {
+    chars: HashSet<char>,
+    classes: Vec<IgnoreClass>,
+    predicate: Option<Box<dyn Fn(char) -> bool + Send + Sync + 'a>>,
+    max_gap: Option<usize>,
+}
+
+impl From<HashSet<char>> for IgnoreChars<'_> {
+    fn from(chars: HashSet<char>) -> Self {
+        Self { chars, ..Self::default() }
+    }
+}
+
+impl From<IgnoreClass> for IgnoreChars<'_> {
+    fn from(class: IgnoreClass) -> Self {
+        Self::new().class(class)
+    }
+}
+
+impl<'a> IgnoreChars<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chars<I: IntoIterator<Item = char>>(mut self, chars: I) -> Self {
+        self.chars.extend(chars);
+        self
+    }
+
+    pub fn class(mut self, class: IgnoreClass) -> Self {
+        if !self.classes.contains(&class) {
+            self.classes.push(class);
+        }
+
+        self
+    }
+
+    pub fn predicate<F: Fn(char) -> bool + Send + Sync + 'a>(mut self, predicate: F) -> Self {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
+    pub fn max_gap(mut self, max_gap: usize) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    pub fn gap_limit(&self) -> Option<usize> {
+        self.max_gap
+    }
+
+    pub fn is_ignored(&self, c: char) -> bool {
+        self.chars.contains(&c) ||
+            self.classes.iter().any(|class| class.contains(c)) ||
+            self.predicate.as_ref().is_some_and(|predicate| predicate(c))
+    }
+}
+
+/**
+ * returns true for the unicode Default_Ignorable_Code_Point chars
+ * they are invisible when rendered, ex: zero width space, zero width joiner, soft hyphen, bidi controls, variation selectors
+ */
+pub fn is_default_ignorable(c: char) -> bool {
+    matches!(c,
+        '\u{ad}' | '\u{34f}' | '\u{61c}' | '\u{115f}'..='\u{1160}' | '\u{17b4}'..='\u{17b5}' | '\u{180b}'..='\u{180f}' |
+        '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{206f}' | '\u{3164}' |
+        '\u{fe00}'..='\u{fe0f}' | '\u{feff}' | '\u{ffa0}' | '\u{fff0}'..='\u{fff8}' |
+        '\u{1bca0}'..='\u{1bca3}' | '\u{1d173}'..='\u{1d17a}' | '\u{e0000}'..='\u{e0fff}')
+}
@@ -1,2 +1,12 @@
 pub mod aho_corasick;
+pub mod aho_corasick_document;
+pub mod aho_corasick_file;
 pub mod aho_corasick_filter;
+pub mod aho_corasick_json;
+pub mod aho_corasick_mask;
+pub mod censor_strategy;
+pub mod filter_profile;
+pub mod filter_term;
+pub mod ignore_chars;
+pub mod normalization;
+pub mod shared_filter;
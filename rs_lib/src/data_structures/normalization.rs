@@ -0,0 +1,330 @@
+use std::cmp::max;
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::char::decompose_canonical;
+use unicode_normalization::char::decompose_compatible;
+use unicode_normalization::char::is_combining_mark;
+use super::aho_corasick::AhoCorasick;
+use crate::error::Error;
+
+/**
+ * the normalizations applied to a text before it is matched
+ * every char is normalized on its own so every normalized char comes from exactly one original char
+ * the text and the patterns are compared in decomposed form with the combining marks in canonical order which matches the same texts as nfc and nfkc
+ * every normalization decomposes the text, ex: with only case_fold "Äpple" matches "A\u{308}pple"
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Normalization {
+    /// compatibility decomposition (nfkd), ex: "ａｐｐｌｅ" becomes "apple" and "ﬁ" becomes "fi"
+    pub compatibility: bool,
+    /// chars are replaced by their lowercase, ex: "ÄPPLE" becomes "äpple"
+    pub case_fold: bool,
+    /// combining marks are removed after the decomposition, ex: "äpple" becomes "apple"
+    pub strip_diacritics: bool,
+}
+
+/**
+ * a normalization that changed a char
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NormalizationRule {
+    Compatibility,
+    CaseFold,
+    StripDiacritics,
+}
+
+impl Normalization {
+    pub fn all() -> Self {
+        Self { compatibility: true, case_fold: true, strip_diacritics: true }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /**
+     * calls output with every normalized char of the char
+     * a char can be normalized to no chars, ex: a combining mark when diacritics are stripped
+     * the combining marks of consecutive chars are not reordered, normalize and NormalizedText reorder them
+     */
+    pub fn apply<F: FnMut(char)>(&self, c: char, mut output: F) {
+        if self.is_identity() {
+            output(c);
+            return;
+        }
+
+        let mut push = |decomposed: char| {
+            if self.case_fold {
+                for lowercase in decomposed.to_lowercase() {
+                    if !(self.strip_diacritics && is_combining_mark(lowercase)) {
+                        output(lowercase);
+                    }
+                }
+            }
+            else if !(self.strip_diacritics && is_combining_mark(decomposed)) {
+                output(decomposed);
+            }
+        };
+
+        if self.compatibility {
+            decompose_compatible(c, &mut push);
+        }
+        else {
+            decompose_canonical(c, &mut push);
+        }
+    }
+
+    /**
+     * returns the enabled normalizations that change the char
+     */
+    pub fn rules_applied(&self, c: char) -> Vec<NormalizationRule> {
+        let mut canonical = Vec::new();
+        decompose_canonical(c, |decomposed| canonical.push(decomposed));
+
+        let mut rules = Vec::new();
+
+        if self.compatibility {
+            let mut compatible = Vec::new();
+            decompose_compatible(c, |decomposed| compatible.push(decomposed));
+
+            if compatible != canonical {
+                rules.push(NormalizationRule::Compatibility);
+            }
+        }
+
+        if self.case_fold && canonical.iter().any(|&decomposed| decomposed.to_lowercase().ne(std::iter::once(decomposed))) {
+            rules.push(NormalizationRule::CaseFold);
+        }
+
+        if self.strip_diacritics && canonical.iter().any(|&decomposed| is_combining_mark(decomposed)) {
+            rules.push(NormalizationRule::StripDiacritics);
+        }
+
+        rules
+    }
+
+    pub fn normalize(&self, string: &str) -> String {
+        if self.is_identity() {
+            return string.to_string();
+        }
+
+        let mut normalized = String::with_capacity(string.len());
+        let mut order = CanonicalOrder::default();
+
+        for c in string.chars() {
+            self.apply(c, |normalized_char| order.push(normalized_char, 0, |ordered, _| normalized.push(ordered)));
+        }
+
+        order.flush(|ordered, _| normalized.push(ordered));
+        normalized
+    }
+}
+
+/**
+ * puts the combining marks that follow a starter in canonical order, the marks are kept until the next starter
+ * every char keeps the index of the original char it comes from
+ */
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CanonicalOrder {
+    marks: Vec<(char, usize)>,
+}
+
+impl CanonicalOrder {
+    pub(crate) fn push<F: FnMut(char, usize)>(&mut self, c: char, origin: usize, mut output: F) {
+        if canonical_combining_class(c) == 0 {
+            self.flush(&mut output);
+            output(c, origin);
+        }
+        else {
+            self.marks.push((c, origin));
+        }
+    }
+
+    pub(crate) fn flush<F: FnMut(char, usize)>(&mut self, mut output: F) {
+        // the sort is stable so the marks of the same class keep their order
+        self.marks.sort_by_key(|&(c, _)| canonical_combining_class(c));
+
+        for (c, origin) in self.marks.drain(..) {
+            output(c, origin);
+        }
+    }
+
+    /**
+     * returns the smallest original index of the marks that are kept
+     */
+    pub(crate) fn first_origin(&self) -> Option<usize> {
+        self.marks.iter().map(|&(_, origin)| origin).min()
+    }
+}
+
+/**
+ * a normalized text with the position in the original text of every normalized char
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedText {
+    pub text: String,
+    // origins[k] is the char index in the original text of the normalized char k
+    origins: Vec<usize>,
+    // byte_indices[i] is the byte index of the original char i, the last element is the byte length of the original text
+    byte_indices: Vec<usize>,
+}
+
+impl NormalizedText {
+    pub fn new(original: &str, normalization: Normalization) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut origins = Vec::with_capacity(original.len());
+        let mut byte_indices = Vec::with_capacity(original.len() + 1);
+
+        let mut order = CanonicalOrder::default();
+        let mut output = |normalized_char: char, origin: usize| {
+            text.push(normalized_char);
+            origins.push(origin);
+        };
+
+        for (i, (byte_index, c)) in original.char_indices().enumerate() {
+            byte_indices.push(byte_index);
+            normalization.apply(c, |normalized_char| order.push(normalized_char, i, &mut output));
+        }
+
+        order.flush(&mut output);
+        byte_indices.push(original.len());
+
+        Self { text, origins, byte_indices }
+    }
+
+    /**
+     * returns the range (start, end) in chars of the original text that the range of normalized chars came from, end is exclusive
+     * the removed chars after the range belong to it like in the filter functions, ex: the stripped mark of "cafe\u{301}"
+     * an empty range maps to an empty range
+     * the reordered combining marks are not in the order of their original chars so the range covers every char of the marks
+     */
+    pub fn original_chars(&self, start: usize, end: usize) -> (usize, usize) {
+        let length = self.byte_indices.len() - 1;
+
+        if end <= start {
+            let original_start = self.origins.get(start).copied().unwrap_or(length);
+            return (original_start, original_start);
+        }
+
+        let range = &self.origins[start..end];
+        let original_start = range.iter().copied().min().unwrap_or(length);
+        let last = range.iter().copied().max().unwrap_or(length);
+
+        // the chars between the last char of the range and the next normalized char produced no normalized char
+        (original_start, max(last + 1, self.origins.get(end).copied().unwrap_or(length)))
+    }
+
+    /**
+     * returns the range (start, end) in bytes of the original text that the range of normalized chars came from, end is exclusive
+     */
+    pub fn original_bytes(&self, start: usize, end: usize) -> (usize, usize) {
+        let (start, end) = self.original_chars(start, end);
+        (self.byte_indices[start], self.byte_indices[end])
+    }
+}
+
+impl AhoCorasick<char> {
+    /**
+     * searches the normalized string, the patterns have to be normalized with the same normalization
+     * returns a vector of tuples (index, length) in chars of the original string
+     */
+    pub fn search_normalized(&self, string: &str, normalization: Normalization) -> Vec<(usize, usize)> {
+        self.try_search_normalized(string, normalization).unwrap_or_default()
+    }
+
+    pub fn try_search_normalized(&self, string: &str, normalization: Normalization) -> Result<Vec<(usize, usize)>, Error> {
+        let normalized = NormalizedText::new(string, normalization);
+
+        Ok(self.try_search(&normalized.text)?
+            .into_iter()
+            .map(|(index, length)| {
+                let (start, end) = normalized.original_chars(index, index + length);
+                (start, end - start)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization_tests() {
+        assert_eq!(Normalization::default().normalize("ＡＰＰＬＥ äpple"), "ＡＰＰＬＥ äpple");
+        assert_eq!(Normalization { compatibility: true, ..Normalization::default() }.normalize("ａｐｐｌｅ ﬁle ①"), "apple file 1");
+        assert_eq!(Normalization { case_fold: true, ..Normalization::default() }.normalize("ÄPPLE Ёж"), "a\u{308}pple е\u{308}ж");
+        assert_eq!(Normalization { strip_diacritics: true, ..Normalization::default() }.normalize("äpple café cafe\u{301} Crème"), "apple cafe cafe Creme");
+        assert_eq!(Normalization::all().normalize("ＡＰＰＬＥ ÄPPLE ﬁLE"), "apple apple file");
+        assert_eq!(Normalization::all().normalize(""), "");
+
+        assert_eq!(Normalization::all().rules_applied('a'), vec![]);
+        assert_eq!(Normalization::all().rules_applied('ａ'), vec![NormalizationRule::Compatibility]);
+        assert_eq!(Normalization::all().rules_applied('Ä'), vec![NormalizationRule::CaseFold, NormalizationRule::StripDiacritics]);
+        assert_eq!(Normalization::all().rules_applied('\u{308}'), vec![NormalizationRule::StripDiacritics]);
+        assert_eq!(Normalization::default().rules_applied('Ä'), vec![]);
+
+        let normalized = NormalizedText::new("ÄＰple ﬁx", Normalization::all());
+        assert_eq!(normalized.text, "apple fix");
+        assert_eq!(normalized.original_chars(0, 5), (0, 5));
+        assert_eq!(normalized.original_bytes(0, 5), (0, 8));
+        assert_eq!(normalized.original_chars(6, 7), (6, 7));
+        assert_eq!(normalized.original_chars(7, 9), (6, 8));
+        assert_eq!(normalized.original_bytes(6, 9), (9, 13));
+        assert_eq!(normalized.original_chars(9, 9), (8, 8));
+
+        // combining marks that are stripped belong to no normalized char
+        let normalized = NormalizedText::new("a\u{308}b", Normalization::all());
+        assert_eq!(normalized.text, "ab");
+        assert_eq!(normalized.original_chars(1, 2), (2, 3));
+        assert_eq!(normalized.original_bytes(0, 1), (0, 3));
+
+        let normalized = NormalizedText::new("cafe\u{301}x", Normalization::all());
+        assert_eq!(normalized.original_chars(0, 4), (0, 5));
+        assert_eq!(normalized.original_chars(4, 5), (5, 6));
+    }
+
+    #[test]
+    fn canonical_order_tests() {
+        let case_fold = Normalization { case_fold: true, ..Normalization::default() };
+
+        // precomposed and decomposed texts have the same normalization
+        assert_eq!(case_fold.normalize("Äpple"), case_fold.normalize("A\u{308}pple"));
+        assert_eq!(case_fold.normalize("äpple"), case_fold.normalize("A\u{308}PPLE"));
+
+        // the marks below (class 220) come before the marks above (class 230) whatever the original order
+        assert_eq!(case_fold.normalize("A\u{301}\u{323}"), "a\u{323}\u{301}");
+        assert_eq!(case_fold.normalize("Ạ\u{301}"), "a\u{323}\u{301}");
+        assert_eq!(case_fold.normalize("Á\u{323}"), "a\u{323}\u{301}");
+        assert_eq!(Normalization::all().normalize("Á\u{323}"), "a");
+
+        let normalized = NormalizedText::new("xÁ\u{323}y", case_fold);
+        assert_eq!(normalized.text, "xa\u{323}\u{301}y");
+        assert_eq!(normalized.original_chars(1, 4), (1, 3));
+        // the mark below comes from the last original char of the marks so the range covers the char before it
+        assert_eq!(normalized.original_chars(1, 3), (1, 3));
+        assert_eq!(normalized.original_chars(4, 5), (3, 4));
+        assert_eq!(normalized.original_bytes(0, 5), (0, 6));
+    }
+
+    #[test]
+    fn aho_corasick_normalized_search_tests() {
+        let normalization = Normalization::all();
+        let mut aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec![normalization.normalize("Apple").as_str(), normalization.normalize("file").as_str()]);
+
+        assert_eq!(aho_corasick.search("ａｐｐｌｅ äpple"), vec![]);
+        assert_eq!(aho_corasick.search_normalized("ａｐｐｌｅ äpple ÄPPLE", normalization), vec![(0, 5), (6, 5), (12, 5)]);
+        assert_eq!(aho_corasick.search_normalized("a\u{308}pple", normalization), vec![(0, 6)]);
+        assert_eq!(aho_corasick.search_normalized("the ﬁle", normalization), vec![(4, 3)]);
+        assert_eq!(aho_corasick.search_normalized("ﬁle\u{301}\u{308}!", normalization), vec![(0, 5)]);
+        assert_eq!(aho_corasick.search_normalized("ｆｉｌｅ", Normalization::default()), vec![]);
+
+        let case_fold = Normalization { case_fold: true, ..Normalization::default() };
+        aho_corasick = AhoCorasick::new();
+        aho_corasick.build(vec![case_fold.normalize("Äpple").as_str(), case_fold.normalize("ạ\u{301}").as_str()]);
+
+        assert_eq!(aho_corasick.search_normalized("äpple A\u{308}PPLE", case_fold), vec![(0, 5), (6, 6)]);
+        assert_eq!(aho_corasick.search_normalized("apple", case_fold), vec![]);
+        assert_eq!(aho_corasick.search_normalized("xÁ\u{323} a\u{301}\u{323}", case_fold), vec![(1, 2), (4, 3)]);
+    }
+}
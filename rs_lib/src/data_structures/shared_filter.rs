@@ -0,0 +1,218 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::Mutex;
+use arc_swap::ArcSwap;
+use super::aho_corasick_filter::AhoCorasickFilter;
+use super::censor_strategy::CensorStrategy;
+use super::filter_profile::FilterProfile;
+use super::filter_term::FilterReport;
+use crate::error::Error;
+
+/**
+ * a profile and the version of the dictionary it was built from, derefs to the filter of the profile
+ */
+pub struct FilterSnapshot {
+    version: u64,
+    profile: FilterProfile,
+}
+
+impl FilterSnapshot {
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn profile(&self) -> &FilterProfile {
+        &self.profile
+    }
+}
+
+impl Deref for FilterSnapshot {
+    type Target = AhoCorasickFilter;
+
+    fn deref(&self) -> &AhoCorasickFilter {
+        &self.profile.filter
+    }
+}
+
+/**
+ * a result and the version of the dictionary that produced it
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Versioned<T> {
+    pub version: u64,
+    pub value: T,
+}
+
+/**
+ * a filter that can be read by many threads while it is replaced
+ * reads never lock, a reload builds the new filter first and then swaps it in atomically
+ * a read that started before a swap finishes with the filter it started with
+ * the filter is stored with the settings of its profile, a filter without a profile gets the defaults of FilterProfile::new
+ *
+ * the first filter is version 1 and every swap increments the version
+ */
+pub struct SharedFilter {
+    current: ArcSwap<FilterSnapshot>,
+    // orders the swaps so the versions always increase, readers never take it
+    swap: Mutex<()>,
+}
+
+impl SharedFilter {
+    pub fn new<P: Into<FilterProfile>>(profile: P) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(FilterSnapshot { version: 1, profile: profile.into() }),
+            swap: Mutex::new(()),
+        }
+    }
+
+    /**
+     * returns the current filter, use the snapshot for several calls that have to see the same dictionary
+     */
+    pub fn load(&self) -> Arc<FilterSnapshot> {
+        self.current.load_full()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.current.load().version
+    }
+
+    /**
+     * replaces the filter or the profile and returns the new version
+     */
+    pub fn store<P: Into<FilterProfile>>(&self, profile: P) -> u64 {
+        let profile = profile.into();
+        let _guard = self.swap.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let version = self.current.load().version + 1;
+        self.current.store(Arc::new(FilterSnapshot { version, profile }));
+
+        version
+    }
+
+    /**
+     * builds a new filter or profile, ex: from a profile file or an api, and swaps it in
+     * returns the new version, the current filter is kept if the build fails
+     */
+    pub fn reload<P: Into<FilterProfile>, F: FnOnce() -> Result<P, Error>>(&self, build: F) -> Result<u64, Error> {
+        Ok(self.store(build()?))
+    }
+
+    /**
+     * filters with the replacement and the ignored chars of the profile
+     */
+    pub fn filter(&self, string: &str) -> Versioned<String> {
+        let snapshot = self.current.load();
+        Versioned { version: snapshot.version, value: snapshot.profile.filter(string) }
+    }
+
+    pub fn try_filter(&self, string: &str) -> Result<Versioned<String>, Error> {
+        let snapshot = self.current.load();
+        Ok(Versioned { version: snapshot.version, value: snapshot.profile.try_filter(string)? })
+    }
+
+    /**
+     * filters with the strategy instead of the replacement, the ignored chars of the profile are still used
+     */
+    pub fn filter_with<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S) -> Versioned<String> {
+        let snapshot = self.current.load();
+        Versioned { version: snapshot.version, value: snapshot.profile.filter.filter_and_ignore_chars(string, strategy, snapshot.profile.ignore()) }
+    }
+
+    pub fn try_filter_with<'a, S: Into<CensorStrategy<'a>>>(&self, string: &str, strategy: S) -> Result<Versioned<String>, Error> {
+        let snapshot = self.current.load();
+        Ok(Versioned { version: snapshot.version, value: snapshot.profile.filter.try_filter_and_ignore_chars(string, strategy, snapshot.profile.ignore())? })
+    }
+
+    pub fn search(&self, text: &str) -> Versioned<Vec<(usize, usize)>> {
+        let snapshot = self.current.load();
+        Versioned { version: snapshot.version, value: snapshot.profile.filter.search(text) }
+    }
+
+    pub fn try_search(&self, text: &str) -> Result<Versioned<Vec<(usize, usize)>>, Error> {
+        let snapshot = self.current.load();
+        Ok(Versioned { version: snapshot.version, value: snapshot.profile.filter.try_search(text)? })
+    }
+
+    pub fn report(&self, string: &str) -> Versioned<FilterReport> {
+        let snapshot = self.current.load();
+        Versioned { version: snapshot.version, value: snapshot.profile.filter.report(string) }
+    }
+
+    pub fn try_report(&self, string: &str) -> Result<Versioned<FilterReport>, Error> {
+        let snapshot = self.current.load();
+        Ok(Versioned { version: snapshot.version, value: snapshot.profile.filter.try_report(string)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn filter_of(words: Vec<&str>) -> AhoCorasickFilter {
+        let mut filter = AhoCorasickFilter::new();
+        filter.build(words);
+        filter
+    }
+
+    #[test]
+    fn shared_filter_tests() {
+        let shared = SharedFilter::new(filter_of(vec!["apple"]));
+        assert_eq!(shared.version(), 1);
+        assert_eq!(shared.filter("apple banana"), Versioned { version: 1, value: "***** banana".to_string() });
+
+        let snapshot = shared.load();
+        assert_eq!(shared.store(filter_of(vec!["banana"])), 2);
+        assert_eq!(shared.filter("apple banana"), Versioned { version: 2, value: "apple ******".to_string() });
+        assert_eq!(shared.filter_with("apple banana", "#").value, "apple ######");
+        assert_eq!(shared.search("a banana").value, vec![(2, 6)]);
+        assert_eq!(shared.report("a banana").version, 2);
+
+        // a snapshot keeps the filter it was loaded with
+        assert_eq!(snapshot.version(), 1);
+        assert_eq!(snapshot.filter("apple banana", "*"), "***** banana");
+
+        // the settings of a reloaded profile are used by the filter functions
+        let profile = "[settings]\ncase = insensitive\nreplacement = #\nignore = .\n[terms]\ncherry";
+        assert_eq!(shared.reload(|| AhoCorasickFilter::from_profile_reader(profile.as_bytes())).unwrap(), 3);
+        assert_eq!(shared.filter("CHERRY c.h.e.r.r.y apple").value, "###### #.#.#.#.#.# apple");
+        assert_eq!(shared.filter_with("c.herry", CensorStrategy::Token("[x]")).value, "[x]");
+        assert_eq!(shared.try_filter("cherry").unwrap().value, "######");
+        assert_eq!(shared.load().profile().replacement, "#");
+
+        // a failed reload keeps the current filter
+        assert!(matches!(shared.reload(|| AhoCorasickFilter::from_profile_reader("[settings]\ncase = upper".as_bytes())), Err(Error::Parse { line: 2, .. })));
+        assert_eq!(shared.version(), 3);
+        assert_eq!(shared.filter("c.herry").value, "#.#####");
+    }
+
+    #[test]
+    fn shared_filter_threads_tests() {
+        let shared = SharedFilter::new(filter_of(vec!["word0"]));
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let mut last = 0;
+
+                    for _ in 0..200 {
+                        let result = shared.filter("word0 word1 word2 word3 word4 word5");
+
+                        // every result is the output of exactly the dictionary of its version
+                        let blocked = format!("word{}", result.version - 1);
+                        assert_eq!(result.value, "word0 word1 word2 word3 word4 word5".replace(&blocked, "*****"));
+                        assert!(result.version >= last);
+                        last = result.version;
+                    }
+                });
+            }
+
+            scope.spawn(|| {
+                for k in 1..6 {
+                    assert_eq!(shared.store(filter_of(vec![format!("word{}", k).as_str()])), k + 1);
+                }
+            });
+        });
+
+        assert_eq!(shared.version(), 6);
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+use std::io;
+
+/**
+ * crate wide error type
+ */
+#[derive(Debug)]
+pub enum Error {
+    /// a node id is referenced that does not exist in the automaton
+    MissingNode(usize),
+    /// a non root node does not have a suffix link, the links were not constructed or were modified
+    MissingSuffixLink(usize),
+    /// a node reports a pattern length that does not fit the searched text
+    InvalidLength(usize),
+    /// a pattern is longer than the configured limit
+    PatternTooLong { length: usize, limit: usize },
+    /// inserting a pattern would create more nodes than the configured limit
+    NodeLimitExceeded { limit: usize },
+    /// reading a file or stream failed
+    Io(io::Error),
+    /// a line of a text format is invalid, the line number starts at 1
+    Parse { line: usize, message: String },
+    /// a weight is NaN, infinite or negative
+    InvalidWeight(f64),
+    /// a json path is empty or has an invalid escape
+    InvalidPath(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingNode(id) => write!(f, "node {} does not exist", id),
+            Error::MissingSuffixLink(id) => write!(f, "node {} does not have a suffix link", id),
+            Error::InvalidLength(id) => write!(f, "node {} has an invalid pattern length", id),
+            Error::PatternTooLong { length, limit } => write!(f, "pattern length {} exceeds the limit of {}", length, limit),
+            Error::NodeLimitExceeded { limit } => write!(f, "node count would exceed the limit of {}", limit),
+            Error::Io(error) => write!(f, "io error: {}", error),
+            Error::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            Error::InvalidWeight(weight) => write!(f, "weight {} is not a finite number of at least 0", weight),
+            Error::InvalidPath(path) => write!(f, "invalid path \"{}\"", path),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
@@ -1,2 +1,3 @@
+pub mod error;
 pub mod string_utils;
 pub mod data_structures;